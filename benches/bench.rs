@@ -28,7 +28,7 @@ fn bench_add_vertices(c: &mut Criterion) {
             let mut graph = black_box(Sodg::<16>::empty(black_box(size)));
             b.iter(|| {
                 for i in 0..size {
-                    black_box(graph.add(black_box(i)));
+                    graph.add(black_box(i));
                 }
                 black_box(&mut graph);
             })
@@ -46,11 +46,7 @@ fn bench_bind_edges(c: &mut Criterion) {
             b.iter(|| {
                 for i in 0..size - 1 {
                     if i % 16 != 0 {
-                        black_box(graph.bind(
-                            black_box(i),
-                            black_box(i + 1),
-                            black_box(Label::Alpha(0)),
-                        ));
+                        graph.bind(black_box(i), black_box(i + 1), black_box(Label::Alpha(0)));
                     }
                 }
                 black_box(&mut graph);
@@ -68,9 +64,7 @@ fn bench_put(c: &mut Criterion) {
             let mut graph = setup_graph(size);
             b.iter(|| {
                 for i in 0..size {
-                    black_box(
-                        graph.put(black_box(i), black_box(&Hex::from_str_bytes("some string"))),
-                    );
+                    graph.put(black_box(i), black_box(&Hex::from_str_bytes("some string")));
                 }
                 black_box(&mut graph);
             })
@@ -87,9 +81,7 @@ fn bench_put_and_data(c: &mut Criterion) {
             let mut graph = setup_graph(size);
             b.iter(|| {
                 for i in 0..size {
-                    black_box(
-                        graph.put(black_box(i), black_box(&Hex::from_str_bytes("some string"))),
-                    );
+                    graph.put(black_box(i), black_box(&Hex::from_str_bytes("some string")));
                     _ = black_box(graph.data(black_box(i)));
                 }
                 black_box(&mut graph);
@@ -5,12 +5,16 @@ use crate::Sodg;
 
 impl<const N: usize> Clone for Sodg<N> {
     /// Make a clone of the graph.
+    ///
+    /// The journal, if it is kept by [`Sodg::journal`], is not cloned.
     fn clone(&self) -> Self {
         Self {
             vertices: self.vertices.clone(),
             branches: self.branches.clone(),
             stores: self.stores.clone(),
             next_v: self.next_v,
+            index: self.index.clone(),
+            journal: None,
         }
     }
 }
@@ -29,6 +33,7 @@ fn makes_a_clone() {
 }
 
 #[test]
+#[allow(clippy::redundant_clone)]
 fn makes_an_empty_clone() {
     let g: Sodg<16> = Sodg::empty(256);
     let c = g.clone();
@@ -0,0 +1,149 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
+// SPDX-License-Identifier: MIT
+
+use crate::{Entry, Hex, Label, Persistence, Sodg};
+#[cfg(debug_assertions)]
+use log::trace;
+use std::collections::HashMap;
+use std::iter;
+
+impl<const N: usize> Sodg<N> {
+    /// Make a copy of the vertex `v` and all its kids, recursively,
+    /// inside the same graph, giving them new IDs from [`Sodg::next_id`].
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("x").unwrap());
+    /// g.bind(1, 0, Label::Greek('ρ'));
+    /// let (copy, _) = g.copy_subtree(1, |_, _, a| a != Label::Greek('ρ'));
+    /// assert_eq!(Some(0), g.kid(copy, Label::Greek('ρ')));
+    /// ```
+    ///
+    /// The predicate `p` is asked about every edge, just like in [`Sodg::bfs`].
+    /// The edges it approves are copied together with the vertices they
+    /// arrive to, while the edges it rejects are shared: the copy
+    /// points to the same vertex as the original, unless this vertex
+    /// is copied too, then the copy points to its copy. For example,
+    /// a `ρ` edge leaving the subtree is shared, while a `ρ` edge
+    /// inside it arrives to the copy of its parent. The data is copied too,
+    /// without submitting the original vertices to garbage collection,
+    /// while the data already taken by [`Sodg::data`] stays taken in the copy.
+    ///
+    /// The ID of the copy of `v` is returned, together with the map
+    /// of all copied vertices to their copies.
+    ///
+    /// # Panics
+    ///
+    /// If the vertex `v` is absent or there are not enough IDs available,
+    /// it will panic.
+    pub fn copy_subtree(
+        &mut self,
+        v: usize,
+        p: impl Fn(usize, usize, Label) -> bool,
+    ) -> (usize, HashMap<usize, usize>) {
+        let order: Vec<usize> = iter::once(v)
+            .chain(self.bfs(v, &p).map(|(_, _, _, to)| to))
+            .collect();
+        let mut copies = HashMap::new();
+        for u in &order {
+            let id = self.next_id();
+            self.add(id);
+            copies.insert(*u, id);
+        }
+        for u in &order {
+            for (a, to) in self.sorted_kids(*u).into_iter().rev() {
+                self.bind(copies[u], copies.get(&to).copied().unwrap_or(to), a);
+            }
+        }
+        for u in &order {
+            let vtx = self.vertices.get(*u).unwrap();
+            let d = vtx.data.clone();
+            match vtx.persistence {
+                Persistence::Empty => {}
+                Persistence::Stored => self.put(copies[u], &d),
+                Persistence::Taken => self.put_taken(copies[u], &d),
+            }
+        }
+        #[cfg(debug_assertions)]
+        trace!(
+            "#copy_subtree: ν{v} copied to ν{}, with {} vertices",
+            copies[&v],
+            copies.len()
+        );
+        (copies[&v], copies)
+    }
+
+    /// Set the data of the vertex `v`, which has no data yet, as if it was
+    /// taken by [`Sodg::data`] already, without garbage collection.
+    pub(crate) fn put_taken(&mut self, v: usize, d: &Hex) {
+        let vtx = self.vertices.get_mut(v).unwrap();
+        if let Some(index) = &mut self.index {
+            index.entry(d.clone()).or_default().insert(v);
+        }
+        vtx.data = d.clone();
+        vtx.persistence = Persistence::Taken;
+        self.log(|| Entry::Taken(v, d.clone()));
+    }
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn copies_subtree_with_data() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.add(2);
+    g.bind(1, 2, Label::from_str("b").unwrap());
+    g.put(2, &Hex::from(42));
+    let (copy, copies) = g.copy_subtree(1, |_, _, _| true);
+    assert_eq!(3, copy);
+    assert_eq!(Some(&4), copies.get(&2));
+    assert_eq!(5, g.len());
+    assert_eq!(Some(4), g.kid(copy, Label::from_str("b").unwrap()));
+    assert_eq!(Hex::from(42), g.data(4).unwrap());
+    assert_eq!(Hex::from(42), g.data(2).unwrap());
+}
+
+#[test]
+fn shares_rejected_edges() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("x").unwrap());
+    g.bind(1, 0, Label::Greek('ρ'));
+    g.add(2);
+    g.bind(1, 2, Label::from_str("y").unwrap());
+    g.bind(2, 1, Label::Greek('ρ'));
+    let (copy, copies) = g.copy_subtree(1, |_, _, a| a != Label::Greek('ρ'));
+    assert_eq!(2, copies.len());
+    let y = g.kid(copy, Label::from_str("y").unwrap()).unwrap();
+    assert_eq!(copies[&2], y);
+    assert_eq!(Some(0), g.kid(copy, Label::Greek('ρ')));
+    assert_eq!(Some(copy), g.kid(y, Label::Greek('ρ')));
+    assert_eq!(Some(1), g.kid(2, Label::Greek('ρ')));
+}
+
+#[test]
+fn keeps_taken_data_taken() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.put(0, &Hex::from(7));
+    g.put(1, &Hex::from(42));
+    g.data(1);
+    let (copy, _) = g.copy_subtree(1, |_, _, _| true);
+    assert!(g.vertices.get(copy).unwrap().persistence == Persistence::Taken);
+    assert_eq!(&1, g.stores.get(g.vertices.get(0).unwrap().branch).unwrap());
+    assert_eq!(Hex::from(42), g.data(copy).unwrap());
+    assert_eq!(3, g.len());
+}
@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
 // SPDX-License-Identifier: MIT
 
-use crate::{Hex, Persistence, Sodg, Vertex, MAX_BRANCHES};
+use crate::{Hex, Persistence, Sodg, Vertex, Vertices, MAX_BRANCHES};
 use emap::Map;
 
 impl<const N: usize> Sodg<N> {
@@ -13,18 +13,12 @@ impl<const N: usize> Sodg<N> {
     #[must_use]
     pub fn empty(cap: usize) -> Self {
         let mut g = Self {
-            vertices: Map::with_capacity_some(
-                cap,
-                Vertex {
-                    branch: 0,
-                    data: Hex::empty(),
-                    persistence: Persistence::Empty,
-                    edges: micromap::Map::new(),
-                },
-            ),
+            vertices: Vertices::with_capacity(cap),
             stores: Map::with_capacity_some(MAX_BRANCHES, 0),
             branches: Map::with_capacity_some(MAX_BRANCHES, microstack::Stack::new()),
             next_v: 0,
+            index: None,
+            journal: None,
         };
         g.branches
             .insert(0, microstack::Stack::from_vec([0].to_vec()));
@@ -34,6 +28,18 @@ impl<const N: usize> Sodg<N> {
     }
 }
 
+impl<const N: usize> Vertex<N> {
+    /// Make an empty [`Vertex`], which is not alive, has no data and no edges.
+    pub(crate) const fn empty() -> Self {
+        Self {
+            branch: 0,
+            data: Hex::empty(),
+            persistence: Persistence::Empty,
+            edges: micromap::Map::new(),
+        }
+    }
+}
+
 #[test]
 fn makes_an_empty_sodg() {
     let mut g: Sodg<16> = Sodg::empty(256);
@@ -0,0 +1,318 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
+// SPDX-License-Identifier: MIT
+
+use crate::{Change, Hex, Label, Persistence, Script, Sodg, SodgDiff};
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+impl<const N: usize> Sodg<N> {
+    /// Find the difference between this graph and the `other` one,
+    /// which is supposed to be a later version of it.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// let mut after = g.clone();
+    /// after.add(1);
+    /// after.bind(0, 1, Label::from_str("foo").unwrap());
+    /// let diff = g.diff(&after);
+    /// assert_eq!("+ν1\n+ν0.foo ➞ ν1", diff.to_string());
+    /// ```
+    ///
+    /// Vertices are matched by their IDs. Changes are ordered so that
+    /// they can be applied one by one: added vertices go first, then
+    /// added and re-targeted edges, then changed and taken data, then removed
+    /// edges, and removed vertices go last. Data taken by [`Sodg::data`] is
+    /// different from the same data not taken yet, just like it is
+    /// for [`PartialEq`].
+    ///
+    /// # Panics
+    ///
+    /// If the graphs have different capacities, it may panic.
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> SodgDiff {
+        let before: HashSet<usize> = self.keys().into_iter().collect();
+        let after: HashSet<usize> = other.keys().into_iter().collect();
+        let mut changes = vec![];
+        for v in other.keys() {
+            if !before.contains(&v) {
+                changes.push(Change::AddVertex(v));
+            }
+        }
+        let mut data = vec![];
+        for v in other.keys() {
+            let vtx = other.vertices.get(v).unwrap();
+            for (a, to) in vtx.edges.iter().sorted() {
+                match self.edge_of(&before, v, *a) {
+                    None => changes.push(Change::AddEdge(v, *a, *to)),
+                    Some(was) if was != *to => {
+                        changes.push(Change::RetargetEdge(v, *a, was, *to));
+                    }
+                    Some(_) => {}
+                }
+            }
+            let old = self.data_of(&before, v);
+            let new = other.data_of(&after, v);
+            let was = self.persistence_of(&before, v);
+            let now = other.persistence_of(&after, v);
+            let same = old == new;
+            if !same || (was == Persistence::Taken && now == Persistence::Stored) {
+                data.push(Change::PutData(v, old, new));
+            }
+            if now == Persistence::Taken && !(same && was == Persistence::Taken) {
+                data.push(Change::TakeData(v));
+            }
+        }
+        changes.extend(data);
+        for v in self.keys() {
+            let vtx = self.vertices.get(v).unwrap();
+            for (a, to) in vtx.edges.iter().sorted() {
+                if other.edge_of(&after, v, *a).is_none() {
+                    changes.push(Change::RemoveEdge(v, *a, *to));
+                }
+            }
+        }
+        for v in self.keys() {
+            if !after.contains(&v) {
+                changes.push(Change::RemoveVertex(v));
+            }
+        }
+        SodgDiff { changes }
+    }
+
+    /// Get the target of an edge, if the vertex is among the `alive` ones.
+    pub(crate) fn edge_of(&self, alive: &HashSet<usize>, v: usize, a: Label) -> Option<usize> {
+        if alive.contains(&v) {
+            self.kid(v, a)
+        } else {
+            None
+        }
+    }
+
+    /// Get the data of a vertex, if it's among the `alive` ones,
+    /// without submitting it to garbage collection.
+    pub(crate) fn data_of(&self, alive: &HashSet<usize>, v: usize) -> Option<Hex> {
+        let vtx = self.vertices.get(v).unwrap();
+        if alive.contains(&v) && vtx.persistence != Persistence::Empty {
+            Some(vtx.data.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Get the persistence of the data of a vertex, if it's among
+    /// the `alive` ones.
+    pub(crate) fn persistence_of(&self, alive: &HashSet<usize>, v: usize) -> Persistence {
+        if alive.contains(&v) {
+            self.vertices.get(v).unwrap().persistence.clone()
+        } else {
+            Persistence::Empty
+        }
+    }
+}
+
+impl SodgDiff {
+    /// Get all changes, in the order they must be applied.
+    #[must_use]
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Get the total number of changes.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Are there no changes at all?
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Make a [`Script`] that applies all the changes to a graph.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// let mut after = g.clone();
+    /// after.add(1);
+    /// after.bind(0, 1, Label::from_str("foo").unwrap());
+    /// g.diff(&after).to_script().unwrap().deploy_to(&mut g).unwrap();
+    /// assert_eq!(after, g);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the data of a vertex was removed, while the vertex
+    /// stays in the graph, an error will be returned, since
+    /// there is no such command in a [`Script`].
+    pub fn to_script(&self) -> Result<Script> {
+        let mut lines = vec![];
+        for c in &self.changes {
+            lines.push(match c {
+                Change::AddVertex(v) => format!("ADD({v});"),
+                Change::RemoveVertex(v) => format!("DROP({v});"),
+                Change::AddEdge(v, a, to) | Change::RetargetEdge(v, a, _, to) => {
+                    format!("BIND({v}, {to}, {a});")
+                }
+                Change::RemoveEdge(v, a, _) => format!("UNBIND({v}, {a});"),
+                Change::PutData(v, _, Some(d)) => format!("PUT({v}, {});", d.print()),
+                Change::PutData(v, _, None) => {
+                    return Err(anyhow!("Can't remove data from ν{v} in a script"));
+                }
+                Change::TakeData(v) => format!("DATA({v});"),
+            });
+        }
+        Ok(Script::from_str(lines.join("\n").as_str()))
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let print = |d: &Option<Hex>| d.as_ref().map_or_else(|| "∅".to_string(), Hex::print);
+        f.write_str(
+            match self {
+                Self::AddVertex(v) => format!("+ν{v}"),
+                Self::RemoveVertex(v) => format!("-ν{v}"),
+                Self::AddEdge(v, a, to) => format!("+ν{v}.{a} ➞ ν{to}"),
+                Self::RemoveEdge(v, a, to) => format!("-ν{v}.{a} ➞ ν{to}"),
+                Self::RetargetEdge(v, a, was, to) => format!("~ν{v}.{a} ➞ ν{was} ⇒ ν{to}"),
+                Self::PutData(v, was, d) => format!("~ν{v}.Δ {} ⇒ {}", print(was), print(d)),
+                Self::TakeData(v) => format!("~ν{v}.Δ taken"),
+            }
+            .as_str(),
+        )
+    }
+}
+
+impl Display for SodgDiff {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(
+            self.changes
+                .iter()
+                .map(ToString::to_string)
+                .join("\n")
+                .as_str(),
+        )
+    }
+}
+
+#[cfg(test)]
+use crate::SodgPatch;
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn finds_no_difference() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("foo").unwrap());
+    assert!(g.diff(&g.clone()).is_empty());
+}
+
+#[test]
+fn finds_all_kinds_of_changes() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.add(2);
+    g.bind(0, 2, Label::from_str("b").unwrap());
+    g.put(2, &Hex::from(1));
+    let mut after = g.clone();
+    after.add(3);
+    after.bind(0, 3, Label::from_str("a").unwrap());
+    after.unbind(0, Label::from_str("b").unwrap());
+    after.remove(1);
+    after.put(2, &Hex::from(2));
+    after.bind(3, 2, Label::from_str("c").unwrap());
+    assert_eq!(
+        vec![
+            "+ν3",
+            "~ν0.a ➞ ν1 ⇒ ν3",
+            "+ν3.c ➞ ν2",
+            "~ν2.Δ 00-00-00-01 ⇒ 00-00-00-02",
+            "-ν0.b ➞ ν2",
+            "-ν1",
+        ],
+        g.diff(&after)
+            .changes()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+    );
+}
+
+#[test]
+fn replays_diff_as_script() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("x").unwrap());
+    g.bind(1, 0, Label::Greek('ρ'));
+    let mut after = g.clone();
+    after.add(2);
+    after.bind(1, 2, Label::from_str("y").unwrap());
+    after.put(2, &Hex::from_str_bytes("hello"));
+    after.unbind(1, Label::Greek('ρ'));
+    let mut s = g.diff(&after).to_script().unwrap();
+    s.deploy_to(&mut g).unwrap();
+    assert_eq!(after, g);
+    assert!(g.diff(&after).is_empty());
+}
+
+#[test]
+fn refuses_to_script_data_removal() {
+    let g: Sodg<16> = Sodg::empty(256);
+    let mut before = g.clone();
+    before.add(0);
+    before.put(0, &Hex::from(1));
+    let mut after = g;
+    after.add(0);
+    assert!(before.diff(&after).to_script().is_err());
+}
+
+#[test]
+fn finds_taken_data() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.put(0, &Hex::from(1));
+    g.put(0, &Hex::from(1));
+    let mut after = g.clone();
+    after.data(0);
+    assert_ne!(g, after);
+    let diff = g.diff(&after);
+    assert_eq!("~ν0.Δ taken", diff.to_string());
+    diff.to_script().unwrap().deploy_to(&mut g).unwrap();
+    assert_eq!(after, g);
+    assert!(g.diff(&after).is_empty());
+}
+
+#[test]
+fn finds_data_stored_again() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.put(0, &Hex::from(1));
+    g.put(0, &Hex::from(1));
+    g.data(0);
+    let mut after = g.clone();
+    after.put(0, &Hex::from(1));
+    let patch = SodgPatch::from(g.diff(&after));
+    assert_eq!(1, patch.changes().len());
+    g.apply(&patch).unwrap();
+    assert_eq!(after, g);
+}
@@ -67,24 +67,16 @@ digraph {
                     e.1,
                     e.0,
                     match e.0 {
-                        Label::Greek(g) => {
-                            if *g == 'ρ' || *g == 'σ' {
-                                ",color=gray,fontcolor=gray"
-                            } else {
-                                ""
-                            }
+                        Label::Greek(g) if *g == 'ρ' || *g == 'σ' => {
+                            ",color=gray,fontcolor=gray"
                         }
                         _ => {
                             ""
                         }
                     },
                     match e.0 {
-                        Label::Greek(g) => {
-                            if *g == 'π' {
-                                ",style=dashed"
-                            } else {
-                                ""
-                            }
+                        Label::Greek(g) if *g == 'π' => {
+                            ",style=dashed"
                         }
                         _ => {
                             ""
@@ -0,0 +1,186 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
+// SPDX-License-Identifier: MIT
+
+use crate::{Hex, Label, Persistence, Sodg};
+use itertools::Itertools;
+use rustc_hash::FxHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::iter;
+
+/// A canonical form of a part of a graph: the data and the edges
+/// of every vertex, where vertices are referred to by their positions.
+type Canonical<'a> = Vec<(Option<&'a Hex>, Vec<(Label, usize)>)>;
+
+impl<const N: usize> PartialEq for Sodg<N> {
+    /// Two graphs are equal if they have the same alive vertices,
+    /// with the same IDs, edges, data, and persistence of the data.
+    ///
+    /// Branches of vertices, the state of [`Sodg::next_id`], and the
+    /// index made by [`Sodg::index_data`] are not compared.
+    fn eq(&self, other: &Self) -> bool {
+        let keys = self.keys();
+        keys == other.keys()
+            && keys.into_iter().all(|v| {
+                let (left, right) = (
+                    self.vertices.get(v).unwrap(),
+                    other.vertices.get(v).unwrap(),
+                );
+                left.persistence == right.persistence
+                    && (left.persistence == Persistence::Empty || left.data == right.data)
+                    && left.edges == right.edges
+            })
+    }
+}
+
+impl<const N: usize> Eq for Sodg<N> {}
+
+impl<const N: usize> Hash for Sodg<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for v in self.keys() {
+            let vtx = self.vertices.get(v).unwrap();
+            v.hash(state);
+            vtx.persistence.hash(state);
+            if vtx.persistence != Persistence::Empty {
+                vtx.data.hash(state);
+            }
+            for e in vtx.edges.iter().sorted() {
+                e.hash(state);
+            }
+        }
+    }
+}
+
+impl<const N: usize> Sodg<N> {
+    /// Check whether the part of this graph reachable from the vertex `v`
+    /// has the same structure as the part of the `other` graph
+    /// reachable from the vertex `u`, no matter what are the IDs of vertices.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("foo").unwrap());
+    /// let mut h : Sodg<16> = Sodg::empty(256);
+    /// h.add(5);
+    /// h.add(7);
+    /// h.bind(5, 7, Label::from_str("foo").unwrap());
+    /// assert!(g.is_isomorphic(0, &h, 5));
+    /// ```
+    ///
+    /// Vertices are matched by the labels of edges, while their
+    /// data is compared by content, no matter whether it was already
+    /// taken by [`Sodg::data`] or not.
+    #[must_use]
+    pub fn is_isomorphic(&self, v: usize, other: &Self, u: usize) -> bool {
+        self.canonical(v) == other.canonical(u)
+    }
+
+    /// Calculate a hash of the content of the part of this graph reachable
+    /// from the vertex `v`, no matter what are the IDs of vertices.
+    ///
+    /// Two parts of graphs have the same hash if they are isomorphic,
+    /// as decided by [`Sodg::is_isomorphic`]. The hash doesn't depend
+    /// on the order of insertions and is the same between runs
+    /// of the program, which makes it suitable as a key for a cache.
+    #[must_use]
+    pub fn content_hash(&self, v: usize) -> u64 {
+        let mut hasher = FxHasher::default();
+        self.canonical(v).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Make a canonical form of the part of the graph reachable from
+    /// the vertex `v`, where vertices are numbered in the order
+    /// they are found by [`Sodg::bfs`].
+    fn canonical(&self, v: usize) -> Canonical<'_> {
+        let order: Vec<usize> = iter::once(v)
+            .chain(self.bfs(v, |_, _, _| true).map(|(_, _, _, to)| to))
+            .collect();
+        let pos: HashMap<usize, usize> = order.iter().enumerate().map(|(i, v)| (*v, i)).collect();
+        order
+            .iter()
+            .map(|v| {
+                let vtx = self.vertices.get(*v).unwrap();
+                (
+                    if vtx.persistence == Persistence::Empty {
+                        None
+                    } else {
+                        Some(&vtx.data)
+                    },
+                    self.sorted_kids(*v)
+                        .into_iter()
+                        .rev()
+                        .map(|(a, to)| (a, pos[&to]))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[cfg(test)]
+use std::collections::HashSet;
+
+#[test]
+#[allow(clippy::mutable_key_type)]
+fn compares_graphs() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.bind(0, 1, Label::from_str("b").unwrap());
+    let mut h: Sodg<16> = Sodg::empty(256);
+    h.add(1);
+    h.add(0);
+    h.bind(0, 1, Label::from_str("b").unwrap());
+    h.bind(0, 1, Label::from_str("a").unwrap());
+    assert_eq!(g, h);
+    let mut set = HashSet::new();
+    set.insert(g.clone());
+    assert!(set.contains(&h));
+    h.put(1, &Hex::from(1));
+    assert_ne!(g, h);
+}
+
+#[test]
+fn distinguishes_persistence() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::Alpha(0));
+    g.put(1, &Hex::from(42));
+    g.put(1, &Hex::from(42));
+    let mut h = g.clone();
+    h.data(1);
+    assert_eq!(g.len(), h.len());
+    assert_ne!(g, h);
+    assert!(g.is_isomorphic(0, &h, 0));
+}
+
+#[test]
+fn checks_isomorphism_with_loops() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("x").unwrap());
+    g.bind(1, 0, Label::Greek('ρ'));
+    g.put(1, &Hex::from_str_bytes("hello"));
+    let mut h: Sodg<16> = Sodg::empty(256);
+    h.add(10);
+    h.add(20);
+    h.bind(20, 10, Label::from_str("x").unwrap());
+    h.bind(10, 20, Label::Greek('ρ'));
+    h.put(10, &Hex::from_str_bytes("hello"));
+    assert!(g.is_isomorphic(0, &h, 20));
+    assert_eq!(g.content_hash(0), h.content_hash(20));
+    assert!(!g.is_isomorphic(0, &h, 10));
+    assert_ne!(g.content_hash(0), h.content_hash(10));
+}
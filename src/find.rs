@@ -0,0 +1,173 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
+// SPDX-License-Identifier: MIT
+
+use crate::{Edge, Label, Sodg};
+use anyhow::{anyhow, Result};
+#[cfg(debug_assertions)]
+use log::trace;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// The label of an edge that points to the decoratee of an object.
+const PHI: Label = Label::Greek('φ');
+
+impl<const N: usize> Sodg<N> {
+    /// Find a vertex by the locator, starting from the vertex `v`.
+    ///
+    /// The locator is a sequence of labels separated by dots, for example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("foo").unwrap());
+    /// g.add(2);
+    /// g.bind(1, 2, Label::from_str("bar").unwrap());
+    /// assert_eq!(2, g.find(0, "foo.bar").unwrap());
+    /// ```
+    ///
+    /// An empty locator points to the vertex `v` itself.
+    ///
+    /// # Errors
+    ///
+    /// If any label in the locator is absent, an error will be returned.
+    pub fn find(&self, v: usize, loc: &str) -> Result<usize> {
+        Ok(self.resolve(v, loc, false)?.0)
+    }
+
+    /// Find a vertex by the locator, starting from the vertex `v`, and
+    /// return it together with the trace of all edges walked through.
+    ///
+    /// If `phi` is `true` and a label is absent in a vertex, the search
+    /// continues in the vertex its `φ` edge points to, the same way EO
+    /// looks up attributes in the decoratee, for example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::Greek('φ'));
+    /// g.add(2);
+    /// g.bind(1, 2, Label::from_str("foo").unwrap());
+    /// let (v, hops) = g.resolve(0, "foo", true).unwrap();
+    /// assert_eq!(2, v);
+    /// assert_eq!(vec![(0, Label::Greek('φ'), 1), (1, Label::from_str("foo").unwrap(), 2)], hops);
+    /// ```
+    ///
+    /// Every element of the trace is a triple of the vertex where
+    /// the hop started, the label of the edge, and the vertex where it ended.
+    ///
+    /// # Errors
+    ///
+    /// If a label is absent, even after all `φ` edges are followed, or
+    /// if `φ` edges make a cycle, an error will be returned.
+    pub fn resolve(&self, v: usize, loc: &str, phi: bool) -> Result<(usize, Vec<Edge>)> {
+        let mut hops = vec![];
+        let mut here = v;
+        for part in loc.split('.').filter(|p| !p.is_empty()) {
+            let a = Label::from_str(part)?;
+            here = self
+                .resolve_one(here, a, phi, &mut hops)
+                .map_err(|e| anyhow!("{e}, while resolving '{loc}' from ν{v}"))?;
+        }
+        #[cfg(debug_assertions)]
+        trace!(
+            "#resolve: ν{v}.{loc} found at ν{here} after {} hop(s)",
+            hops.len()
+        );
+        Ok((here, hops))
+    }
+
+    /// Walk one edge with the label `a`, from the vertex `v`, optionally
+    /// falling back to `φ` edges, and record the hops made.
+    ///
+    /// # Errors
+    ///
+    /// If the label is absent or a cycle of `φ` edges is detected,
+    /// an error will be returned.
+    fn resolve_one(&self, v: usize, a: Label, phi: bool, hops: &mut Vec<Edge>) -> Result<usize> {
+        let mut seen = HashSet::new();
+        let mut here = v;
+        loop {
+            seen.insert(here);
+            if let Some(to) = self.kid(here, a) {
+                hops.push((here, a, to));
+                return Ok(to);
+            }
+            if !phi || a == PHI {
+                return Err(anyhow!("Can't find .{a} in ν{here}"));
+            }
+            let Some(to) = self.kid(here, PHI) else {
+                return Err(anyhow!(
+                    "Can't find .{a} in ν{v}, even after {} φ hop(s)",
+                    seen.len() - 1
+                ));
+            };
+            if seen.contains(&to) {
+                return Err(anyhow!(
+                    "Can't find .{a} in ν{v}, the φ of ν{here} makes a cycle at ν{to}"
+                ));
+            }
+            hops.push((here, PHI, to));
+            here = to;
+        }
+    }
+}
+
+#[test]
+fn finds_by_locator() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("org").unwrap());
+    g.add(2);
+    g.bind(1, 2, Label::from_str("eolang").unwrap());
+    assert_eq!(2, g.find(0, "org.eolang").unwrap());
+    assert_eq!(0, g.find(0, "").unwrap());
+    assert!(g.find(0, "org.foo").is_err());
+}
+
+#[test]
+fn ignores_phi_when_not_asked() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, PHI);
+    g.add(2);
+    g.bind(1, 2, Label::from_str("x").unwrap());
+    assert!(g.find(0, "x").is_err());
+    assert_eq!(2, g.find(0, "φ.x").unwrap());
+}
+
+#[test]
+fn follows_phi_chain() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, PHI);
+    g.add(2);
+    g.bind(1, 2, PHI);
+    g.add(3);
+    g.bind(2, 3, Label::from_str("x").unwrap());
+    g.add(4);
+    g.bind(3, 4, Label::from_str("y").unwrap());
+    let (v, hops) = g.resolve(0, "x.y", true).unwrap();
+    assert_eq!(4, v);
+    assert_eq!(4, hops.len());
+    assert_eq!((1, PHI, 2), hops[1]);
+}
+
+#[test]
+fn detects_phi_cycle() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, PHI);
+    g.bind(1, 0, PHI);
+    let msg = g.resolve(0, "x", true).unwrap_err().to_string();
+    assert!(msg.contains("cycle"), "{msg}");
+}
@@ -4,6 +4,7 @@
 use crate::{Hex, HEX_SIZE};
 use anyhow::{Context, Result};
 use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
 use std::ops::{
     Index, IndexMut, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive,
 };
@@ -24,6 +25,12 @@ impl PartialEq for Hex {
 
 impl Eq for Hex {}
 
+impl Hash for Hex {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bytes().hash(state);
+    }
+}
+
 impl Display for Hex {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str(self.print().as_str())
@@ -997,5 +1004,5 @@ fn test_from_str_bytes_correctly() {
     let a = Hex::from_str_bytes("Hello, world!");
     let b = &a[5..];
     let res = String::from_utf8(b.to_vec()).unwrap();
-    assert_eq!(&res, ", world!")
+    assert_eq!(&res, ", world!");
 }
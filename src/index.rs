@@ -0,0 +1,155 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
+// SPDX-License-Identifier: MIT
+
+use crate::{Hex, Persistence, Sodg};
+#[cfg(debug_assertions)]
+use log::trace;
+use std::collections::{HashMap, HashSet};
+
+impl<const N: usize> Sodg<N> {
+    /// Find all alive vertices that hold exactly this data.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use sodg::{Hex, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.put(1, &Hex::from_str_bytes("hello"));
+    /// assert_eq!(vec![1], g.find_by_data(&Hex::from_str_bytes("hello")));
+    /// ```
+    ///
+    /// Unlike [`Sodg::data`], this function doesn't submit vertices to
+    /// garbage collection. If the index is enabled by [`Sodg::index_data`],
+    /// it is used instead of scanning all vertices. The vertices found are sorted.
+    #[must_use]
+    pub fn find_by_data(&self, d: &Hex) -> Vec<usize> {
+        if let Some(index) = &self.index {
+            let mut found: Vec<usize> = index
+                .get(d)
+                .map(|all| {
+                    all.iter()
+                        .copied()
+                        .filter(|v| self.holds(*v, |h| h == d))
+                        .collect()
+                })
+                .unwrap_or_default();
+            found.sort_unstable();
+            return found;
+        }
+        self.find_by_data_if(|h| h == d)
+    }
+
+    /// Find all alive vertices with data approved by the predicate.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use sodg::{Hex, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.put(0, &Hex::from(42_i64));
+    /// g.add(1);
+    /// g.put(1, &Hex::from(7_i64));
+    /// assert_eq!(vec![0], g.find_by_data_if(|d| d.to_i64().unwrap() > 10));
+    /// ```
+    ///
+    /// All vertices are scanned, while none of them are submitted
+    /// to garbage collection. The vertices found are sorted.
+    #[must_use]
+    pub fn find_by_data_if(&self, p: impl Fn(&Hex) -> bool) -> Vec<usize> {
+        self.keys()
+            .into_iter()
+            .filter(|v| self.holds(*v, &p))
+            .collect()
+    }
+
+    /// Enable the index of vertices by their data, which makes
+    /// [`Sodg::find_by_data`] faster.
+    ///
+    /// The index is built from the data of all vertices and then
+    /// kept up to date by [`Sodg::put`]. It is not saved to files by [`Sodg::save`].
+    pub fn index_data(&mut self) {
+        let mut index: HashMap<Hex, HashSet<usize>> = HashMap::new();
+        for (v, vtx) in self.vertices.iter() {
+            if vtx.persistence != Persistence::Empty {
+                index.entry(vtx.data.clone()).or_default().insert(v);
+            }
+        }
+        #[cfg(debug_assertions)]
+        trace!("#index_data: {} distinct data indexed", index.len());
+        self.index = Some(index);
+    }
+
+    /// Disable the index of vertices by their data, made by [`Sodg::index_data`].
+    pub fn drop_index(&mut self) {
+        self.index = None;
+    }
+
+    /// Is this vertex alive and does it hold data approved by the predicate?
+    fn holds(&self, v: usize, p: impl Fn(&Hex) -> bool) -> bool {
+        self.vertices.get(v).is_some_and(|vtx| {
+            vtx.branch != 0 && vtx.persistence != Persistence::Empty && p(&vtx.data)
+        })
+    }
+}
+
+#[test]
+fn finds_by_data_without_index() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.put(0, &Hex::from_str_bytes("hello"));
+    g.add(1);
+    g.put(1, &Hex::from_str_bytes("bye"));
+    g.add(2);
+    g.put(2, &Hex::from_str_bytes("hello"));
+    assert_eq!(vec![0, 2], g.find_by_data(&Hex::from_str_bytes("hello")));
+    assert!(g.vertices.get(0).unwrap().persistence == Persistence::Stored);
+}
+
+#[test]
+fn keeps_index_up_to_date() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.put(0, &Hex::from(1_i64));
+    g.index_data();
+    g.add(1);
+    g.put(1, &Hex::from(1_i64));
+    assert_eq!(vec![0, 1], g.find_by_data(&Hex::from(1_i64)));
+    g.put(0, &Hex::from(2_i64));
+    assert_eq!(vec![1], g.find_by_data(&Hex::from(1_i64)));
+    assert_eq!(vec![0], g.find_by_data(&Hex::from(2_i64)));
+    g.drop_index();
+    assert_eq!(vec![0], g.find_by_data(&Hex::from(2_i64)));
+}
+
+#[test]
+fn skips_collected_vertices() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.index_data();
+    g.add(1);
+    g.add(2);
+    g.bind(1, 2, crate::Label::Alpha(0));
+    g.put(2, &Hex::from_str_bytes("hello"));
+    assert_eq!(vec![2], g.find_by_data(&Hex::from_str_bytes("hello")));
+    g.data(2);
+    assert!(g.find_by_data(&Hex::from_str_bytes("hello")).is_empty());
+}
+
+#[test]
+fn forgets_collected_vertices() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.index_data();
+    g.add(1);
+    g.add(2);
+    g.bind(1, 2, crate::Label::Alpha(0));
+    g.put(1, &Hex::from(7_i64));
+    g.put(2, &Hex::from_str_bytes("hello"));
+    g.data(1);
+    g.data(2);
+    assert_eq!(0, g.len());
+    let index = g.index.as_ref().unwrap();
+    assert!(index[&Hex::from(7_i64)].is_empty());
+    assert!(index[&Hex::from_str_bytes("hello")].is_empty());
+}
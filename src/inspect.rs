@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
 // SPDX-License-Identifier: MIT
 
-use crate::Sodg;
+use crate::{Label, Sodg};
 use anyhow::{Context, Result};
 use itertools::Itertools;
 use std::collections::HashSet;
@@ -16,45 +16,61 @@ impl<const N: usize> Sodg<N> {
     ///
     /// If it's impossible to inspect, an error will be returned.
     pub fn inspect(&self, v: usize) -> Result<String> {
-        let mut seen = HashSet::new();
-        Ok(format!(
-            "ν{}\n{}",
-            v,
-            self.inspect_v(v, &mut seen)?.join("\n")
-        ))
+        self.vertices
+            .get(v)
+            .with_context(|| format!("Can't find ν{v}"))?;
+        let tree: HashSet<(usize, Label)> = self
+            .dfs(v, |_, _, _| true)
+            .map(|(_, from, a, _)| (from, a))
+            .collect();
+        Ok(format!("ν{}\n{}", v, self.inspect_v(v, &tree).join("\n")))
+    }
+
+    /// Print the shortest path from the vertex `from` to the vertex `to`
+    /// as a locator, like `ν0.org.eolang.int`.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("org").unwrap());
+    /// g.add(2);
+    /// g.bind(1, 2, Label::from_str("eolang").unwrap());
+    /// assert_eq!("ν0.org.eolang", g.locate(0, 2).unwrap());
+    /// ```
+    ///
+    /// If `to` is not reachable from `from`, `None` is returned.
+    #[must_use]
+    pub fn locate(&self, from: usize, to: usize) -> Option<String> {
+        self.path(from, to).map(|path| {
+            path.iter()
+                .fold(format!("ν{from}"), |loc, (_, a)| format!("{loc}.{a}"))
+        })
     }
 
-    fn inspect_v(&self, v: usize, seen: &mut HashSet<usize>) -> Result<Vec<String>> {
-        seen.insert(v);
+    /// Print the kids of the vertex `v`, going deeper only through
+    /// the edges found in the `tree` made by [`Sodg::dfs`].
+    fn inspect_v(&self, v: usize, tree: &HashSet<(usize, Label)>) -> Vec<String> {
         let mut lines = vec![];
-        self.vertices
-            .get(v)
-            .with_context(|| format!("Can't find ν{v}"))?
-            .edges
-            .iter()
-            .sorted()
-            .for_each(|e| {
-                let skip = seen.contains(e.1);
-                let line = format!(
-                    "  .{} ➞ ν{}{}",
-                    e.0,
-                    e.1,
-                    if skip {
-                        "…".to_string()
-                    } else {
-                        String::new()
-                    }
-                );
-                lines.push(line);
-                if !skip {
-                    seen.insert(*e.1);
-                    self.inspect_v(*e.1, seen)
-                        .unwrap()
-                        .iter()
-                        .for_each(|t| lines.push(format!("  {t}")));
-                }
-            });
-        Ok(lines)
+        for (a, to) in self.kids(v).sorted() {
+            let deeper = tree.contains(&(v, *a));
+            lines.push(format!(
+                "  .{} ➞ ν{}{}",
+                a,
+                to,
+                if deeper { "" } else { "…" }
+            ));
+            if deeper {
+                self.inspect_v(*to, tree)
+                    .iter()
+                    .for_each(|t| lines.push(format!("  {t}")));
+            }
+        }
+        lines
     }
 }
 
@@ -62,7 +78,7 @@ impl<const N: usize> Sodg<N> {
 use crate::Hex;
 
 #[cfg(test)]
-use crate::Label;
+use std::str::FromStr;
 
 #[test]
 fn inspects_simple_object() {
@@ -74,3 +90,30 @@ fn inspects_simple_object() {
     g.bind(0, 1, Label::Alpha(0));
     assert_ne!(String::new(), txt);
 }
+
+#[test]
+fn inspects_loops() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.bind(0, 1, Label::from_str("b").unwrap());
+    g.bind(1, 0, Label::from_str("c").unwrap());
+    assert_eq!(
+        "ν0\n  .a ➞ ν1\n    .c ➞ ν0…\n  .b ➞ ν1…",
+        g.inspect(0).unwrap()
+    );
+}
+
+#[test]
+fn locates_vertex() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(7);
+    g.bind(0, 7, Label::from_str("x").unwrap());
+    g.add(81);
+    g.bind(7, 81, Label::from_str("y").unwrap());
+    assert_eq!("ν0.x.y", g.locate(0, 81).unwrap());
+    assert_eq!("ν0", g.locate(0, 0).unwrap());
+    assert!(g.locate(81, 0).is_none());
+}
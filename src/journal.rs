@@ -0,0 +1,498 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
+// SPDX-License-Identifier: MIT
+
+use crate::{Entry, Journal, Sodg};
+use anyhow::{anyhow, Context, Result};
+use bincode::{deserialize, serialize};
+use log::{debug, trace};
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The file of the checkpoint of the generation.
+fn checkpoint_of(dir: &Path, generation: u64) -> PathBuf {
+    dir.join(format!("{generation}.sodg"))
+}
+
+/// The file of the log, which goes after the checkpoint of the generation.
+fn log_of(dir: &Path, generation: u64) -> PathBuf {
+    dir.join(format!("{generation}.log"))
+}
+
+/// Append the entry to the log, as a record: its size and its checksum,
+/// both as little-endian `u32`, followed by the entry, serialized.
+///
+/// # Errors
+///
+/// If impossible to write, an error will be returned.
+fn append<W: Write>(mut w: W, entry: &Entry) -> Result<()> {
+    let bytes = serialize(entry)?;
+    w.write_all(&u32::try_from(bytes.len())?.to_le_bytes())?;
+    w.write_all(&crc32fast::hash(&bytes).to_le_bytes())?;
+    w.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Read all entries from the log, one by one, stopping at the first
+/// record which is not written entirely or is broken.
+fn entries(mut log: &[u8]) -> Vec<Entry> {
+    let mut all = vec![];
+    while log.len() >= 8 {
+        let (size, rest) = log.split_at(4);
+        let (checksum, rest) = rest.split_at(4);
+        let size = u32::from_le_bytes(size.try_into().unwrap()) as usize;
+        if rest.len() < size {
+            break;
+        }
+        let (bytes, rest) = rest.split_at(size);
+        if crc32fast::hash(bytes) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+            break;
+        }
+        let Ok(entry) = deserialize(bytes) else {
+            break;
+        };
+        all.push(entry);
+        log = rest;
+    }
+    all
+}
+
+/// Find numbers of all checkpoints in the directory, sorted.
+///
+/// # Errors
+///
+/// If impossible to read the directory, an error will be returned.
+fn generations(dir: &Path) -> Result<Vec<u64>> {
+    let mut all = vec![];
+    for entry in fs::read_dir(dir).with_context(|| format!("Can't read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "sodg") {
+            if let Some(g) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<u64>().ok())
+            {
+                all.push(g);
+            }
+        }
+    }
+    all.sort_unstable();
+    Ok(all)
+}
+
+impl<const N: usize> Sodg<N> {
+    /// Start keeping the journal of all modifications in the directory.
+    ///
+    /// First, the entire graph is saved into the directory by [`Sodg::save`],
+    /// as a checkpoint. Then, every modification made by [`Sodg::add`],
+    /// [`Sodg::bind`], [`Sodg::put`], [`Sodg::unbind`], [`Sodg::remove`],
+    /// and [`Sodg::data`] is appended to the log, as a binary record, together
+    /// with every move of the state of [`Sodg::next_id`].
+    /// After `period` records, a new checkpoint is made and the log starts
+    /// from scratch. If `period` is zero, checkpoints are made only
+    /// by [`Sodg::checkpoint`]. After a crash, the graph may be restored
+    /// by [`Sodg::recover`], for example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let tmp = tempfile::TempDir::new().unwrap();
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.journal(tmp.path(), 1000).unwrap();
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("foo").unwrap());
+    /// g.sync().unwrap();
+    /// let after : Sodg<16> = Sodg::recover(tmp.path()).unwrap();
+    /// assert_eq!(g, after);
+    /// ```
+    ///
+    /// Other modifications, made by [`Sodg::apply`], [`Sodg::merge`],
+    /// [`Sodg::merge_with`], and [`Sodg::merge_three_way`], are not appended
+    /// to the log: instead, a checkpoint is made right after each of them,
+    /// if it succeeds.
+    ///
+    /// The log is buffered. After a crash, all entries appended before
+    /// the latest [`Sodg::sync`] or the latest checkpoint are recovered.
+    /// Entries appended after them may be lost, but only at the end
+    /// of the log: if the process crashes, the entries still in the buffer
+    /// are lost, while if the machine crashes, the entries not yet synced
+    /// to the disk may be lost too.
+    ///
+    /// If it's impossible to append to the log or to make a periodic
+    /// checkpoint, nothing is appended any more, until the next checkpoint,
+    /// and the failure is returned by [`Sodg::sync`].
+    ///
+    /// # Errors
+    ///
+    /// If impossible to save the checkpoint, an error will be returned.
+    pub fn journal(&mut self, dir: &Path, period: usize) -> Result<()> {
+        fs::create_dir_all(dir).with_context(|| format!("Can't create {}", dir.display()))?;
+        let generation = generations(dir)?.last().map_or(0, |g| g + 1);
+        self.rotate(dir, generation, period)
+    }
+
+    /// Make a checkpoint of the journal right now, saving the entire graph
+    /// and starting the log from scratch.
+    ///
+    /// Since the checkpoint contains the entire graph, the failure
+    /// to append to the log, if it happened earlier, is forgotten.
+    ///
+    /// # Errors
+    ///
+    /// If the journal is not kept or if impossible to save
+    /// the checkpoint, an error will be returned.
+    pub fn checkpoint(&mut self) -> Result<()> {
+        let j = self
+            .journal
+            .as_ref()
+            .ok_or_else(|| anyhow!("The journal is not kept, can't make a checkpoint"))?;
+        let (dir, generation, period) = (j.dir.clone(), j.generation + 1, j.period);
+        self.rotate(&dir, generation, period)
+    }
+
+    /// Make sure all entries appended to the journal are on the disk,
+    /// so that [`Sodg::recover`] finds them after a crash.
+    ///
+    /// # Errors
+    ///
+    /// If the journal is not kept, if it failed to append an entry
+    /// or to make a periodic checkpoint earlier, or if impossible
+    /// to sync the log, an error will be returned.
+    pub fn sync(&mut self) -> Result<()> {
+        let j = self
+            .journal
+            .as_mut()
+            .ok_or_else(|| anyhow!("The journal is not kept, can't sync it"))?;
+        if let Some(e) = &j.failure {
+            return Err(anyhow!(
+                "The journal in {} is broken, make a checkpoint: {e:#}",
+                j.dir.display()
+            ));
+        }
+        j.log
+            .flush()
+            .and_then(|()| j.log.get_ref().sync_data())
+            .with_context(|| format!("Can't sync the journal in {}", j.dir.display()))
+    }
+
+    /// Restore the graph from the directory, where the journal was kept
+    /// by [`Sodg::journal`].
+    ///
+    /// The latest checkpoint, which is not broken, is loaded and then its log
+    /// is replayed. The records at the end of the log, which were not written
+    /// entirely or are broken, are ignored. The graph returned doesn't keep
+    /// the journal: call [`Sodg::journal`] in order to continue.
+    ///
+    /// # Errors
+    ///
+    /// If there are no checkpoints in the directory, or if
+    /// the log can't be read, an error will be returned.
+    ///
+    /// # Panics
+    ///
+    /// If the log doesn't fit the checkpoint, for example, if it refers
+    /// to vertices outside of its capacity, it may panic.
+    pub fn recover(dir: &Path) -> Result<Self> {
+        for generation in generations(dir)?.into_iter().rev() {
+            let mut g = match Self::load(&checkpoint_of(dir, generation)) {
+                Ok(g) => g,
+                Err(e) => {
+                    debug!("The checkpoint no.{generation} is broken: {e:#}");
+                    continue;
+                }
+            };
+            let log = log_of(dir, generation);
+            if !log.exists() {
+                return Ok(g);
+            }
+            let bytes = fs::read(&log).with_context(|| format!("Can't read {}", log.display()))?;
+            let all = entries(&bytes);
+            let total = all.len();
+            for entry in all {
+                g.replay(entry);
+            }
+            debug!(
+                "Recovered {} vertices from the checkpoint no.{generation} and {total} entries of its log",
+                g.len()
+            );
+            return Ok(g);
+        }
+        Err(anyhow!("There are no checkpoints in {}", dir.display()))
+    }
+
+    /// Append an entry to the journal, if it is kept and didn't fail, and
+    /// make a checkpoint, if it's time. The failure, if any, is recorded
+    /// in the journal, to be returned by [`Sodg::sync`].
+    #[inline]
+    pub(crate) fn log(&mut self, entry: impl FnOnce() -> Entry) {
+        if let Some(j) = &mut self.journal {
+            if j.failure.is_some() {
+                return;
+            }
+            if let Err(e) = append(&mut j.log, &entry()) {
+                j.failure = Some(e.context("Can't append to the journal"));
+                return;
+            }
+            j.entries += 1;
+            if j.entries == j.period {
+                self.renew();
+            }
+        }
+    }
+
+    /// Make the modification, which the entry stands for.
+    fn replay(&mut self, entry: Entry) {
+        match entry {
+            Entry::Add(v) => self.add(v),
+            Entry::Bind(v1, v2, a) => self.bind(v1, v2, a),
+            Entry::Put(v, d) => self.put(v, &d),
+            Entry::Data(v) => {
+                self.data(v);
+            }
+            Entry::Taken(v, d) => self.put_taken(v, &d),
+            Entry::Unbind(v, a) => self.unbind(v, a),
+            Entry::Remove(v) => self.remove(v),
+            Entry::Next(next) => self.next_v = next,
+        }
+    }
+
+    /// Run the modification, which is not appended to the journal entry
+    /// by entry, and then make a checkpoint, if the journal is kept
+    /// and the modification succeeds.
+    ///
+    /// # Errors
+    ///
+    /// If the modification fails, its error will be returned.
+    pub(crate) fn checkpointed<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let journal = self.journal.take();
+        let ret = f(self);
+        self.journal = journal;
+        if ret.is_ok() {
+            self.renew();
+        }
+        ret
+    }
+
+    /// Make a checkpoint, if the journal is kept, recording
+    /// the failure in the journal, if any.
+    fn renew(&mut self) {
+        if self.journal.is_some() {
+            if let Err(e) = self.checkpoint() {
+                if let Some(j) = &mut self.journal {
+                    j.failure.get_or_insert(e);
+                }
+            }
+        }
+    }
+
+    /// Save the checkpoint of the generation, start a new log after it,
+    /// and delete all older checkpoints and logs.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to save, an error will be returned.
+    fn rotate(&mut self, dir: &Path, generation: u64, period: usize) -> Result<()> {
+        self.save(&checkpoint_of(dir, generation))?;
+        let path = log_of(dir, generation);
+        let log = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)
+            .with_context(|| format!("Can't create {}", path.display()))?;
+        self.journal = Some(Journal {
+            dir: dir.to_path_buf(),
+            log: BufWriter::new(log),
+            generation,
+            period,
+            entries: 0,
+            failure: None,
+        });
+        for old in generations(dir)?.into_iter().filter(|g| *g < generation) {
+            for path in [checkpoint_of(dir, old), log_of(dir, old)] {
+                if path.exists() {
+                    fs::remove_file(&path)
+                        .with_context(|| format!("Can't delete {}", path.display()))?;
+                }
+            }
+        }
+        trace!(
+            "Checkpoint no.{generation} of {} vertices saved to {}",
+            self.len(),
+            dir.display()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+use crate::{Hex, Label};
+
+#[cfg(test)]
+use tempfile::TempDir;
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn recovers_after_crash() {
+    let tmp = TempDir::new().unwrap();
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.journal(tmp.path(), 0).unwrap();
+    g.add(1);
+    g.add(2);
+    g.bind(0, 1, Label::from_str("foo").unwrap());
+    g.bind(0, 2, Label::from_str("bar").unwrap());
+    g.put(1, &Hex::from(42));
+    g.put(2, &Hex::from(7));
+    g.data(2);
+    g.unbind(0, Label::from_str("bar").unwrap());
+    g.remove(2);
+    g.sync().unwrap();
+    let after: Sodg<16> = Sodg::recover(tmp.path()).unwrap();
+    assert_eq!(g, after);
+}
+
+#[test]
+fn makes_checkpoints_periodically() {
+    let tmp = TempDir::new().unwrap();
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.journal(tmp.path(), 3).unwrap();
+    for v in 0..10 {
+        g.add(v);
+    }
+    g.sync().unwrap();
+    assert_eq!(vec![3], generations(tmp.path()).unwrap());
+    assert_eq!(1, entries(&fs::read(log_of(tmp.path(), 3)).unwrap()).len());
+    let after: Sodg<16> = Sodg::recover(tmp.path()).unwrap();
+    assert_eq!(10, after.len());
+}
+
+#[test]
+fn ignores_torn_entry() {
+    let tmp = TempDir::new().unwrap();
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.journal(tmp.path(), 0).unwrap();
+    g.add(0);
+    g.add(1);
+    g.sync().unwrap();
+    let mut log = OpenOptions::new()
+        .append(true)
+        .open(log_of(tmp.path(), 0))
+        .unwrap();
+    let mut record = vec![];
+    append(
+        &mut record,
+        &Entry::Bind(0, 1, Label::from_str("foo").unwrap()),
+    )
+    .unwrap();
+    log.write_all(&record[..record.len() - 1]).unwrap();
+    let after: Sodg<16> = Sodg::recover(tmp.path()).unwrap();
+    assert_eq!(g, after);
+}
+
+#[test]
+fn skips_broken_checkpoint() {
+    let tmp = TempDir::new().unwrap();
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.journal(tmp.path(), 0).unwrap();
+    g.add(0);
+    g.sync().unwrap();
+    fs::write(checkpoint_of(tmp.path(), 1), b"SODG").unwrap();
+    let after: Sodg<16> = Sodg::recover(tmp.path()).unwrap();
+    assert_eq!(g, after);
+    assert!(Sodg::<16>::recover(&tmp.path().join("absent")).is_err());
+}
+
+#[test]
+fn recovers_after_merge() {
+    let tmp = TempDir::new().unwrap();
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.add(2);
+    g.bind(0, 2, Label::from_str("b").unwrap());
+    g.journal(tmp.path(), 0).unwrap();
+    let mut extra = Sodg::empty(256);
+    extra.add(0);
+    extra.add(1);
+    extra.bind(0, 1, Label::from_str("a").unwrap());
+    extra.bind(0, 1, Label::from_str("b").unwrap());
+    extra.add(2);
+    extra.bind(1, 2, Label::from_str("x").unwrap());
+    g.merge(&extra, 0, 0).unwrap();
+    let after: Sodg<16> = Sodg::recover(tmp.path()).unwrap();
+    assert_eq!(g, after);
+    let a = after.kid(0, Label::from_str("a").unwrap()).unwrap();
+    assert_eq!(Some(a), after.kid(0, Label::from_str("b").unwrap()));
+    assert!(after.kid(a, Label::from_str("x").unwrap()).is_some());
+}
+
+#[test]
+fn reports_failure_on_sync() {
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().join("journal");
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.journal(&dir, 2).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    g.add(0);
+    g.add(1);
+    g.add(2);
+    assert!(g.sync().is_err());
+    fs::create_dir_all(&dir).unwrap();
+    g.checkpoint().unwrap();
+    g.sync().unwrap();
+    let after: Sodg<16> = Sodg::recover(&dir).unwrap();
+    assert_eq!(g, after);
+}
+
+#[test]
+fn keeps_labels_with_any_chars() {
+    let tmp = TempDir::new().unwrap();
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.journal(tmp.path(), 0).unwrap();
+    g.add(0);
+    g.add(1);
+    for a in ["a#b", "x,y", "p;q", "f)"] {
+        g.bind(0, 1, Label::from_str(a).unwrap());
+    }
+    g.add(2);
+    g.sync().unwrap();
+    let after: Sodg<16> = Sodg::recover(tmp.path()).unwrap();
+    assert_eq!(g, after);
+    assert_eq!(Some(1), after.kid(0, Label::from_str("a#b").unwrap()));
+}
+
+#[test]
+fn keeps_state_of_next_id() {
+    let tmp = TempDir::new().unwrap();
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.journal(tmp.path(), 0).unwrap();
+    let v = g.next_id();
+    g.add(v);
+    g.remove(v);
+    g.sync().unwrap();
+    let mut after: Sodg<16> = Sodg::recover(tmp.path()).unwrap();
+    assert_eq!(g.next_id(), after.next_id());
+}
+
+#[test]
+fn keeps_copies_of_taken_data() {
+    let tmp = TempDir::new().unwrap();
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.journal(tmp.path(), 0).unwrap();
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.put(0, &Hex::from(7));
+    g.put(1, &Hex::from(42));
+    g.data(1);
+    g.copy_subtree(0, |_, _, _| true);
+    g.sync().unwrap();
+    let after: Sodg<16> = Sodg::recover(tmp.path()).unwrap();
+    assert_eq!(g, after);
+}
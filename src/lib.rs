@@ -27,23 +27,35 @@
 #![allow(clippy::multiple_crate_versions)]
 
 mod clone;
+mod copy;
 mod ctors;
 mod debug;
+mod diff;
 mod dot;
+mod eq;
+mod find;
 mod hex;
+mod index;
 mod inspect;
+mod journal;
 mod label;
 mod merge;
 mod misc;
 mod next;
 mod ops;
+mod patch;
+mod query;
 mod script;
 mod serialization;
 mod slice;
+mod topology;
+mod traverse;
+mod vertices;
+mod view;
 mod xml;
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 const HEX_SIZE: usize = 8;
 const MAX_BRANCHES: usize = 16;
@@ -81,6 +93,189 @@ pub enum Label {
     Str([char; 8]),
 }
 
+/// An edge between two vertices: the vertex it departs from, its label,
+/// and the vertex it arrives to.
+pub type Edge = (usize, Label, usize);
+
+/// An iterator over the vertices reachable from a given one,
+/// in breadth-first order, made by [`Sodg::bfs`].
+///
+/// Every item is a tuple of the depth of the vertex found, the vertex
+/// it was found from, the label of the edge between them, and the vertex itself.
+pub struct Bfs<'a, const N: usize, P> {
+    /// The graph being traversed.
+    g: &'a Sodg<N>,
+    /// The predicate that decides which edges to walk through.
+    p: P,
+    /// The vertices already discovered.
+    seen: HashSet<usize>,
+    /// The vertices discovered, but not yet returned.
+    todo: VecDeque<(usize, usize, Label, usize)>,
+}
+
+/// An iterator over the vertices reachable from a given one,
+/// in depth-first order, made by [`Sodg::dfs`].
+///
+/// Every item is a tuple of the depth of the vertex found, the vertex
+/// it was found from, the label of the edge between them, and the vertex itself.
+pub struct Dfs<'a, const N: usize, P> {
+    /// The graph being traversed.
+    g: &'a Sodg<N>,
+    /// The predicate that decides which edges to walk through.
+    p: P,
+    /// The vertices already discovered.
+    seen: HashSet<usize>,
+    /// The vertices being visited, with the depth of each of them and
+    /// the edges not yet walked through, in reverse order.
+    #[allow(clippy::type_complexity)]
+    stack: Vec<(usize, usize, Vec<(Label, usize)>)>,
+}
+
+/// A pattern of a vertex, to be found in a [`Sodg`] by [`Sodg::query`].
+///
+/// For example, this is how you find all vertices with a `Δ` edge
+/// whose target holds exactly eight bytes of data, binding
+/// the target to the name `d`:
+///
+/// ```
+/// use sodg::{Hex, Label, Pattern, Sodg};
+/// let mut g : Sodg<16> = Sodg::empty(256);
+/// g.add(0);
+/// g.add(1);
+/// g.bind(0, 1, Label::Greek('Δ'));
+/// g.put(1, &Hex::from(42_i64));
+/// let p = Pattern::Edge(
+///     Label::Greek('Δ'),
+///     Box::new(Pattern::Bind("d".to_string(), Box::new(Pattern::Size(8)))),
+/// );
+/// let found = g.query(&p);
+/// assert_eq!(1, found.len());
+/// assert_eq!(0, found[0].0);
+/// assert_eq!(1, found[0].1["d"]);
+/// ```
+#[derive(Clone)]
+pub enum Pattern {
+    /// Any vertex.
+    Any,
+    /// A vertex with any data.
+    Data,
+    /// A vertex with exactly this data.
+    Is(Hex),
+    /// A vertex with data of this size, in bytes.
+    Size(usize),
+    /// A vertex with an edge under this label, pointing to a vertex
+    /// that matches the pattern.
+    Edge(Label, Box<Self>),
+    /// A vertex with at least one edge pointing to a vertex that matches the pattern.
+    Kid(Box<Self>),
+    /// A vertex that matches all the patterns.
+    All(Vec<Self>),
+    /// A vertex that matches at least one of the patterns.
+    Either(Vec<Self>),
+    /// A vertex that doesn't match the pattern.
+    Not(Box<Self>),
+    /// A vertex that matches the pattern and gets bound to the name.
+    Bind(String, Box<Self>),
+}
+
+/// A single change of a [`Sodg`], as found by [`Sodg::diff`].
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Change {
+    /// A vertex was added.
+    AddVertex(usize),
+    /// A vertex was removed.
+    RemoveVertex(usize),
+    /// An edge was added.
+    AddEdge(usize, Label, usize),
+    /// An edge was removed.
+    RemoveEdge(usize, Label, usize),
+    /// An edge departing from a vertex, with a label, now arrives to
+    /// another vertex: the first one is the old target, the second one is the new one.
+    RetargetEdge(usize, Label, usize, usize),
+    /// The data of a vertex was changed: the first one is the old data,
+    /// the second one is the new data.
+    PutData(usize, Option<Hex>, Option<Hex>),
+    /// The data of a vertex was taken by [`Sodg::data`], and the vertex
+    /// was submitted to garbage collection.
+    TakeData(usize),
+}
+
+/// The difference between two graphs, made by [`Sodg::diff`].
+///
+/// It can be printed in a human-readable form or turned into
+/// a [`Script`], which applies the changes, by [`SodgDiff::to_script`].
+pub struct SodgDiff {
+    /// All changes, in the order they must be applied.
+    changes: Vec<Change>,
+}
+
+/// A set of changes to be applied to a [`Sodg`] by [`Sodg::apply`].
+///
+/// Every change carries the expectations about the graph it is
+/// applied to, for example, the old target of a re-targeted edge. A patch
+/// is usually made from a [`SodgDiff`], for example:
+///
+/// ```
+/// use std::str::FromStr;
+/// use sodg::{Label, Sodg, SodgPatch};
+/// let mut g : Sodg<16> = Sodg::empty(256);
+/// g.add(0);
+/// let mut after = g.clone();
+/// after.add(1);
+/// after.bind(0, 1, Label::from_str("foo").unwrap());
+/// let patch = SodgPatch::from(g.diff(&after));
+/// g.apply(&patch).unwrap();
+/// assert_eq!(after, g);
+/// ```
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SodgPatch {
+    /// All changes, in the order they must be applied.
+    changes: Vec<Change>,
+}
+
+/// A conflict found by [`Sodg::merge_with`] or [`Sodg::merge_three_way`],
+/// when the current graph and the graph being merged into it don't agree.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MergeConflict {
+    /// An edge departing from a vertex, with a label, arrives to one vertex
+    /// in the current graph (the first one) and to another vertex in the merged
+    /// graph (the second one); `None` means that there is no such edge.
+    Edge(usize, Label, Option<usize>, Option<usize>),
+    /// A vertex has one data in the current graph (the first one) and
+    /// another data in the merged graph (the second one); `None` means
+    /// that there is no data.
+    Data(usize, Option<Hex>, Option<Hex>),
+}
+
+/// The result of [`Sodg::merge`] and [`Sodg::merge_with`].
+#[derive(Clone, Debug, Default)]
+pub struct MergeReport {
+    /// Vertices of the merged graph mapped to the vertices of the current one.
+    mapping: HashMap<usize, usize>,
+    /// Vertices added to the current graph.
+    added: HashSet<usize>,
+    /// The number of vertices joined with others.
+    joined: usize,
+    /// The number of vertices with data overwritten.
+    overwritten: usize,
+}
+
+/// A strategy of resolving a [`MergeConflict`] by [`Sodg::merge_with`]
+/// or [`Sodg::merge_three_way`].
+pub enum MergeStrategy {
+    /// Keep what is in the current graph.
+    PreferLeft,
+    /// Take what is in the graph being merged.
+    PreferRight,
+    /// Stop merging and return an error.
+    Fail,
+    /// Ask the function, which returns `true` to take what is in the graph
+    /// being merged, `false` to keep what is in the current graph,
+    /// or an error to stop merging.
+    #[allow(clippy::type_complexity)]
+    Custom(Box<dyn Fn(&MergeConflict) -> anyhow::Result<bool>>),
+}
+
 /// A wrapper of a plain text with graph-modifying instructions.
 ///
 /// For example, you can pass the following instructions to it:
@@ -125,13 +320,121 @@ pub struct Script {
 pub struct Sodg<const N: usize> {
     stores: emap::Map<usize>,
     branches: emap::Map<microstack::Stack<usize, MAX_BRANCH_SIZE>>,
-    vertices: emap::Map<Vertex<N>>,
+    vertices: Vertices<N>,
     /// This is the next ID of a vertex to be returned by the [`Sodg::next_v`] function.
     #[serde(skip_serializing, skip_deserializing)]
     next_v: usize,
+    /// The index of vertices by their data, if it's enabled by [`Sodg::index_data`].
+    #[serde(skip_serializing, skip_deserializing)]
+    index: Option<HashMap<Hex, HashSet<usize>>>,
+    /// The journal of modifications, if it's enabled by [`Sodg::journal`].
+    #[serde(skip_serializing, skip_deserializing)]
+    journal: Option<Journal>,
+}
+
+/// A journal of modifications of a [`Sodg`], kept in a directory
+/// together with the latest checkpoint, see [`Sodg::journal`].
+struct Journal {
+    /// The directory with checkpoints and logs.
+    dir: std::path::PathBuf,
+    /// The log being appended.
+    log: std::io::BufWriter<std::fs::File>,
+    /// The number of the latest checkpoint.
+    generation: u64,
+    /// How many entries to append before the next checkpoint.
+    period: usize,
+    /// How many entries are appended since the latest checkpoint.
+    entries: usize,
+    /// The first failure to append or to make a checkpoint, after which
+    /// nothing is appended until the next checkpoint.
+    failure: Option<anyhow::Error>,
+}
+
+/// A record of the log of a [`Journal`], one per modification.
+#[derive(Serialize, Deserialize)]
+enum Entry {
+    /// The vertex is added by [`Sodg::add`].
+    Add(usize),
+    /// The edge is made by [`Sodg::bind`].
+    Bind(usize, usize, Label),
+    /// The data is set by [`Sodg::put`].
+    Put(usize, Hex),
+    /// The data is taken by [`Sodg::data`].
+    Data(usize),
+    /// The data, which is taken already, is copied by [`Sodg::copy_subtree`].
+    Taken(usize, Hex),
+    /// The edge is removed by [`Sodg::unbind`].
+    Unbind(usize, Label),
+    /// The vertex is removed by [`Sodg::remove`].
+    Remove(usize),
+    /// The state of [`Sodg::next_id`] is moved forward.
+    Next(usize),
+}
+
+/// A read-only view of a [`Sodg`], saved by [`Sodg::save_view`] into
+/// a file and memory-mapped by [`SodgView::open`].
+///
+/// Nothing is deserialized when the file is opened: edges and data
+/// are read right from the mapping, when asked for, for example:
+///
+/// ```
+/// use std::str::FromStr;
+/// use sodg::{Hex, Label, Sodg, SodgView};
+/// let mut g : Sodg<16> = Sodg::empty(256);
+/// g.add(0);
+/// g.add(1);
+/// g.bind(0, 1, Label::from_str("foo").unwrap());
+/// g.put(1, &Hex::from(42));
+/// let tmp = tempfile::TempDir::new().unwrap();
+/// let file = tmp.path().join("foo.sodv");
+/// g.save_view(file.as_path()).unwrap();
+/// let view : SodgView<16> = SodgView::open(file.as_path()).unwrap();
+/// assert_eq!(Some(1), view.kid(0, Label::from_str("foo").unwrap()));
+/// assert_eq!(Hex::from(42), view.data(1).unwrap());
+/// ```
+///
+/// In order to modify the graph, promote the view to a [`Sodg`]
+/// through [`SodgView::promote`].
+#[derive(Clone)]
+pub struct SodgView<const N: usize> {
+    /// The file, mapped into memory.
+    map: std::sync::Arc<memmap2::Mmap>,
+    /// All labels of edges found in the graph, sorted.
+    labels: Vec<Label>,
+    /// The total number of vertices the graph may have.
+    capacity: usize,
+    /// The number of entries of alive vertices in the mapping.
+    entries: usize,
+    /// The position of the first edge in the mapping.
+    edges: usize,
+    /// The position of the first byte of data in the mapping.
+    data: usize,
+}
+
+/// All vertices of a [`Sodg`], alive or not, found by their IDs.
+///
+/// If the [`Sodg`] is promoted from a [`SodgView`], the vertices
+/// not modified yet are read from the view, see [`Fallback`].
+#[derive(Clone)]
+struct Vertices<const N: usize> {
+    /// The vertices.
+    map: emap::Map<Vertex<N>>,
+    /// The view, where vertices not copied into the map yet are read from.
+    fallback: Option<Box<Fallback<N>>>,
+}
+
+/// The view behind the [`Vertices`] of a promoted [`Sodg`].
+#[derive(Clone)]
+struct Fallback<const N: usize> {
+    /// The view.
+    view: SodgView<N>,
+    /// Which vertices are copied into the map already, one bit per vertex.
+    copied: Vec<u64>,
+    /// The vertices read from the view, but not copied into the map yet.
+    read: Vec<std::sync::OnceLock<Vertex<N>>>,
 }
 
-#[derive(PartialEq, Serialize, Deserialize, Clone)]
+#[derive(PartialEq, Eq, Hash, Serialize, Deserialize, Clone)]
 enum Persistence {
     Empty,
     Stored,
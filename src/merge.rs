@@ -1,59 +1,385 @@
 // SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
 // SPDX-License-Identifier: MIT
 
-use crate::{Label, Persistence, Sodg};
+use crate::{
+    Change, Hex, Label, MergeConflict, MergeReport, MergeStrategy, Persistence, Sodg, SodgPatch,
+};
 use anyhow::{anyhow, Result};
 use log::debug;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+impl MergeStrategy {
+    /// Decide whether the conflict must be resolved in favor of the graph
+    /// being merged (`true`) or the current graph (`false`).
+    ///
+    /// # Errors
+    ///
+    /// If the conflict can't be resolved, an error will be returned.
+    fn prefers_right(&self, c: &MergeConflict) -> Result<bool> {
+        match self {
+            Self::PreferLeft => Ok(false),
+            Self::PreferRight => Ok(true),
+            Self::Fail => Err(anyhow!("Can't merge, due to conflict in {c}")),
+            Self::Custom(f) => f(c),
+        }
+    }
+}
+
+impl MergeReport {
+    /// Get the vertices of the merged graph, mapped to the vertices
+    /// of the current one.
+    #[must_use]
+    pub const fn mapping(&self) -> &HashMap<usize, usize> {
+        &self.mapping
+    }
+
+    /// Get the number of vertices added to the current graph.
+    #[must_use]
+    pub fn added(&self) -> usize {
+        self.added.len()
+    }
+
+    /// Get the number of vertices of the current graph, which were
+    /// joined with other vertices and then removed.
+    #[must_use]
+    pub const fn joined(&self) -> usize {
+        self.joined
+    }
+
+    /// Get the number of vertices, which had data, overwritten
+    /// by different data from the merged graph.
+    #[must_use]
+    pub const fn overwritten(&self) -> usize {
+        self.overwritten
+    }
+}
+
+impl Display for MergeConflict {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let vertex = |t: &Option<usize>| t.map_or_else(|| "∅".to_string(), |t| format!("ν{t}"));
+        let data = |d: &Option<Hex>| d.as_ref().map_or_else(|| "∅".to_string(), Hex::print);
+        f.write_str(
+            match self {
+                Self::Edge(v, a, left, right) => {
+                    format!("ν{v}.{a}: {} vs. {}", vertex(left), vertex(right))
+                }
+                Self::Data(v, left, right) => {
+                    format!("ν{v}.Δ: {} vs. {}", data(left), data(right))
+                }
+            }
+            .as_str(),
+        )
+    }
+}
 
 impl<const N: usize> Sodg<N> {
     /// Merge another graph into the current one.
     ///
-    /// It is expected that both graphs are trees! If they are not, the result is unpredictable.
-    ///
     /// The `right` vertex is mapped to the `left` vertex. The decisions about
-    /// their kids are made recursively.
+    /// their kids are made recursively: a kid of the `right` vertex is mapped
+    /// to the kid of the `left` vertex with the same label, if it exists,
+    /// otherwise a new vertex is added. Graphs don't have to be trees: if a
+    /// vertex of the right graph turns out to be mapped to two different
+    /// vertices of the current graph, they are joined into one.
     ///
     /// The `left` vertex is expected
     /// to be the root of the current graph, while the `right` vertex is the root
     /// of the graph being merged into the current one.
     ///
+    /// Conflicting edges stop the merge, while conflicting data is taken
+    /// from the graph being merged. Use [`Sodg::merge_with`] to change this.
+    ///
     /// # Errors
     ///
-    /// If it's impossible to merge, an error will be returned.
-    pub fn merge(&mut self, g: &Self, left: usize, right: usize) -> Result<()> {
-        let mut mapped = HashMap::new();
-        let before = self.len();
-        self.merge_rec(g, left, right, &mut mapped)?;
-        let merged = mapped.len();
-        let scope = g.len();
-        if merged != scope {
-            let must = g.keys();
-            let seen = mapped.keys().copied().collect::<Vec<usize>>();
-            let missed: HashSet<usize> =
-                &HashSet::from_iter(must.clone()) - &HashSet::from_iter(seen.clone());
-            let mut ordered: Vec<usize> = missed.into_iter().collect();
-            ordered.sort_unstable();
-            return Err(anyhow!(
-                "Just {merged} vertices merged, out of {scope} (must={}, seen={}); maybe the right graph was not a tree? {} missed: {}",
-                must.len(), seen.len(),
-                ordered.len(), ordered.iter().map(|v| format!("ν{v}")).collect::<Vec<String>>().join(", ")
-            ));
+    /// If it's impossible to merge, an error will be returned
+    /// and the current graph stays intact.
+    pub fn merge(&mut self, g: &Self, left: usize, right: usize) -> Result<MergeReport> {
+        self.merge_with(
+            g,
+            left,
+            right,
+            &MergeStrategy::Fail,
+            &MergeStrategy::PreferRight,
+        )
+    }
+
+    /// Merge another graph into the current one, resolving conflicts
+    /// of edges and data with the strategies provided.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use sodg::{Hex, MergeStrategy, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.put(0, &Hex::from(1));
+    /// let mut extra = Sodg::empty(256);
+    /// extra.add(0);
+    /// extra.put(0, &Hex::from(2));
+    /// g.merge_with(&extra, 0, 0, &MergeStrategy::Fail, &MergeStrategy::PreferLeft).unwrap();
+    /// assert_eq!(Hex::from(1), g.data(0).unwrap());
+    /// ```
+    ///
+    /// Edges conflict when two vertices of the current graph are joined
+    /// into one, while they both have edges with the same label, but
+    /// arriving to different vertices. Data conflicts when a vertex
+    /// gets data, which is different from the data it already has.
+    ///
+    /// The merge is atomic: the graph is merged into a clone of
+    /// the current one, which replaces it only if the merge succeeds.
+    /// If the journal is kept by [`Sodg::journal`], a checkpoint is made
+    /// right after that.
+    ///
+    /// # Errors
+    ///
+    /// If it's impossible to merge, or a conflict can't be resolved,
+    /// an error will be returned and the current graph stays intact.
+    pub fn merge_with(
+        &mut self,
+        g: &Self,
+        left: usize,
+        right: usize,
+        edges: &MergeStrategy,
+        data: &MergeStrategy,
+    ) -> Result<MergeReport> {
+        self.checkpointed(|sodg| {
+            let mut report = MergeReport::default();
+            let before = sodg.len();
+            let mut target = sodg.clone();
+            target.merge_rec(g, left, right, &mut report, edges, data)?;
+            let merged = report.mapping.len();
+            let scope = g.len();
+            if merged != scope {
+                let must = g.keys();
+                let seen = report.mapping.keys().copied().collect::<Vec<usize>>();
+                let missed: HashSet<usize> =
+                    &HashSet::from_iter(must.clone()) - &HashSet::from_iter(seen.clone());
+                let mut ordered: Vec<usize> = missed.into_iter().collect();
+                ordered.sort_unstable();
+                return Err(anyhow!(
+                    "Just {merged} vertices merged, out of {scope} (must={}, seen={}); \
+                     {} of them are not reachable from ν{right}: {}",
+                    must.len(),
+                    seen.len(),
+                    ordered.len(),
+                    ordered
+                        .iter()
+                        .map(|v| format!("ν{v}"))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ));
+            }
+            *sodg = target;
+            debug!(
+                "Merged all {merged} vertices into SODG of {}, making it have {} after the merge",
+                before,
+                sodg.len()
+            );
+            Ok(report)
+        })
+    }
+
+    /// Merge the changes made in the `other` graph into the current one,
+    /// provided both of them were made from the `base` graph.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, MergeStrategy, Sodg};
+    /// let mut base : Sodg<16> = Sodg::empty(256);
+    /// base.add(0);
+    /// let mut ours = base.clone();
+    /// ours.add(1);
+    /// ours.bind(0, 1, Label::from_str("a").unwrap());
+    /// let mut theirs = base.clone();
+    /// theirs.add(2);
+    /// theirs.bind(0, 2, Label::from_str("b").unwrap());
+    /// let conflicts = ours.merge_three_way(
+    ///     &base, &theirs, &MergeStrategy::Fail, &MergeStrategy::Fail,
+    /// ).unwrap();
+    /// assert!(conflicts.is_empty());
+    /// assert_eq!(Some(2), ours.kid(0, Label::from_str("b").unwrap()));
+    /// ```
+    ///
+    /// Vertices present in the `base` are matched by their IDs, while vertices
+    /// added in the `other` graph get new IDs, the way [`Sodg::next_id`] gives
+    /// them, since the same IDs may be taken by other vertices added in the
+    /// current graph. Every edge and every data, changed
+    /// only in the `other` graph, is changed in the current one too. If it
+    /// was changed in both graphs, differently, it is a conflict, which is resolved
+    /// by the strategy. Changes to vertices removed in the current graph
+    /// are ignored, as well as removals of vertices changed in it or
+    /// pointed to by new edges in it. All changes
+    /// are applied by [`Sodg::apply`], atomically. If the journal is kept
+    /// by [`Sodg::journal`], a checkpoint is made right after the merge.
+    ///
+    /// All conflicts found are returned, no matter how they were resolved.
+    ///
+    /// # Errors
+    ///
+    /// If a conflict can't be resolved, or there is no room for vertices
+    /// added in the `other` graph, an error will be returned
+    /// and the current graph stays intact.
+    pub fn merge_three_way(
+        &mut self,
+        base: &Self,
+        other: &Self,
+        edges: &MergeStrategy,
+        data: &MergeStrategy,
+    ) -> Result<Vec<MergeConflict>> {
+        self.checkpointed(|sodg| {
+            let before: HashSet<usize> = base.keys().into_iter().collect();
+            let current: HashSet<usize> = sodg.keys().into_iter().collect();
+            let touched = sodg.touched_since(base);
+            let dropped = |v: &usize| before.contains(v) && !current.contains(v);
+            let (fresh, next) = sodg.fresh_ids(
+                other.keys().into_iter().filter(|v| !before.contains(v)),
+                &current,
+            )?;
+            let id = |v: usize| fresh.get(&v).copied().unwrap_or(v);
+            let mut changes = vec![];
+            let mut conflicts = vec![];
+            for c in base.diff(other).changes() {
+                match c {
+                    Change::AddVertex(v) => changes.push(Change::AddVertex(id(*v))),
+                    Change::RemoveVertex(v) => {
+                        if current.contains(v) && !touched.contains(v) {
+                            changes.push(Change::RemoveVertex(*v));
+                        }
+                    }
+                    Change::AddEdge(v, a, _)
+                    | Change::RetargetEdge(v, a, _, _)
+                    | Change::RemoveEdge(v, a, _) => {
+                        let theirs = other.kid(*v, *a);
+                        if dropped(v) || theirs.is_some_and(|t| dropped(&t)) {
+                            continue;
+                        }
+                        let theirs = theirs.map(id);
+                        let v = &id(*v);
+                        let ours = sodg.edge_of(&current, *v, *a);
+                        if ours == theirs {
+                            continue;
+                        }
+                        if ours != base.edge_of(&before, *v, *a) {
+                            let conflict = MergeConflict::Edge(*v, *a, ours, theirs);
+                            let right = edges.prefers_right(&conflict)?;
+                            conflicts.push(conflict);
+                            if !right {
+                                continue;
+                            }
+                        }
+                        changes.push(match (ours, theirs) {
+                            (None, Some(t)) => Change::AddEdge(*v, *a, t),
+                            (Some(o), Some(t)) => Change::RetargetEdge(*v, *a, o, t),
+                            (Some(o), None) => Change::RemoveEdge(*v, *a, o),
+                            (None, None) => unreachable!(),
+                        });
+                    }
+                    Change::PutData(v, _, theirs) => {
+                        if dropped(v) {
+                            continue;
+                        }
+                        let v = &id(*v);
+                        let ours = sodg.data_of(&current, *v);
+                        if ours == *theirs {
+                            continue;
+                        }
+                        if ours != base.data_of(&before, *v) {
+                            let conflict = MergeConflict::Data(*v, ours.clone(), theirs.clone());
+                            let right = data.prefers_right(&conflict)?;
+                            conflicts.push(conflict);
+                            if !right {
+                                continue;
+                            }
+                        }
+                        changes.push(Change::PutData(*v, ours, theirs.clone()));
+                    }
+                    Change::TakeData(v) => {
+                        if dropped(v) {
+                            continue;
+                        }
+                        let v = &id(*v);
+                        let put = changes
+                            .iter()
+                            .any(|c| matches!(c, Change::PutData(w, _, Some(_)) if w == v));
+                        if put || sodg.persistence_of(&current, *v) == Persistence::Stored {
+                            changes.push(Change::TakeData(*v));
+                        }
+                    }
+                }
+            }
+            let total = changes.len();
+            sodg.apply(&SodgPatch::new(changes))?;
+            if !fresh.is_empty() {
+                sodg.next_v = next;
+            }
+            debug!(
+                "Merged {total} changes in three ways, {} conflicts found",
+                conflicts.len()
+            );
+            Ok(conflicts)
+        })
+    }
+
+    /// Find vertices, which edges or data were changed since the `base`,
+    /// together with vertices pointed to by edges added or re-targeted.
+    fn touched_since(&self, base: &Self) -> HashSet<usize> {
+        base.diff(self)
+            .changes()
+            .iter()
+            .filter_map(|c| match c {
+                Change::AddEdge(v, _, to) | Change::RetargetEdge(v, _, _, to) => {
+                    Some(vec![*v, *to])
+                }
+                Change::RemoveEdge(v, _, _) | Change::PutData(v, _, _) | Change::TakeData(v) => {
+                    Some(vec![*v])
+                }
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Find new IDs for the vertices, starting from the state of
+    /// [`Sodg::next_id`] and skipping the vertices `taken`, without
+    /// changing the graph. The IDs found are returned together with
+    /// the state of [`Sodg::next_id`] after them.
+    ///
+    /// # Errors
+    ///
+    /// If there is not enough room in the graph, an error will be returned.
+    fn fresh_ids(
+        &self,
+        vertices: impl Iterator<Item = usize>,
+        taken: &HashSet<usize>,
+    ) -> Result<(HashMap<usize, usize>, usize)> {
+        let mut fresh = HashMap::new();
+        let mut next = self.next_v;
+        for v in vertices {
+            while taken.contains(&next) {
+                next += 1;
+            }
+            if next >= self.vertices.capacity() {
+                return Err(anyhow!(
+                    "There is no room for ν{v} in the graph of {} vertices",
+                    self.vertices.capacity()
+                ));
+            }
+            fresh.insert(v, next);
+            next += 1;
         }
-        debug!(
-            "Merged all {merged} vertices into SODG of {}, making it have {} after the merge",
-            before,
-            self.len()
-        );
-        Ok(())
+        Ok((fresh, next))
     }
 
-    /// Merge two trees recursively, ignoring the nodes already `mapped`.
+    /// Merge two graphs recursively, ignoring the nodes already `mapped`.
     ///
     /// The `right` vertex is mapped to the `left` vertex. The decisions about
     /// their kids are made recursively.
     ///
-    /// The `mapped` is a key-value map, where the key is a vertex from the right
+    /// The `report.mapping` is a key-value map, where the key is a vertex from the right
     /// graph, which is mapped to a vertex from the left graph.
     ///
     /// # Errors
@@ -65,34 +391,38 @@ impl<const N: usize> Sodg<N> {
         g: &Self,
         left: usize,
         right: usize,
-        mapped: &mut HashMap<usize, usize>,
+        report: &mut MergeReport,
+        edges: &MergeStrategy,
+        data: &MergeStrategy,
     ) -> Result<()> {
-        if mapped.contains_key(&right) {
+        if report.mapping.contains_key(&right) {
             return Ok(());
         }
-        mapped.insert(right, left);
-        if g.vertices.get(right).unwrap().persistence != Persistence::Empty {
-            self.put(left, &g.vertices.get(right).unwrap().data);
+        report.mapping.insert(right, left);
+        let vtx = g.vertices.get(right).unwrap();
+        if vtx.persistence != Persistence::Empty {
+            self.put_merged(left, &vtx.data, report, data)?;
         }
         for (a, to) in g.kids(right) {
             let matched = if let Some(t) = self.kid(left, *a) {
                 t
-            } else if let Some(t) = mapped.get(to) {
+            } else if let Some(t) = report.mapping.get(to) {
                 self.bind(left, *t, *a);
                 *t
             } else {
                 let id = self.next_id();
                 self.add(id);
                 self.bind(left, id, *a);
+                report.added.insert(id);
                 id
             };
-            self.merge_rec(g, matched, *to, mapped)?;
+            self.merge_rec(g, matched, *to, report, edges, data)?;
         }
         for (a, to) in g.kids(right) {
             if let Some(first) = self.kid(left, *a) {
-                if let Some(second) = mapped.get(to) {
+                if let Some(second) = report.mapping.get(to) {
                     if first != *second {
-                        self.join(first, *second);
+                        self.join(first, *second, report, edges, data)?;
                     }
                 }
             }
@@ -100,29 +430,94 @@ impl<const N: usize> Sodg<N> {
         Ok(())
     }
 
-    fn join(&mut self, left: usize, right: usize) {
+    /// Put the data into the vertex `v`, resolving the conflict with
+    /// the data it already has, if any.
+    ///
+    /// # Errors
+    ///
+    /// If the conflict can't be resolved, an error will be returned.
+    fn put_merged(
+        &mut self,
+        v: usize,
+        d: &Hex,
+        report: &mut MergeReport,
+        data: &MergeStrategy,
+    ) -> Result<()> {
+        let vtx = self.vertices.get(v).unwrap();
+        if vtx.persistence == Persistence::Empty || vtx.data == *d {
+            self.put(v, d);
+        } else if data.prefers_right(&MergeConflict::Data(
+            v,
+            Some(vtx.data.clone()),
+            Some(d.clone()),
+        ))? {
+            self.put(v, d);
+            report.overwritten += 1;
+        }
+        Ok(())
+    }
+
+    /// Join the vertex `right` into the vertex `left`.
+    ///
+    /// All edges pointing to `right` are re-targeted to `left`, all kids
+    /// of `right` are moved to `left`, and then `right` is removed. The data
+    /// of `right` is moved to `left` too. All vertices in
+    /// `report.mapping`, which were mapped to `right`, are re-mapped to `left`.
+    ///
+    /// # Errors
+    ///
+    /// If a conflict can't be resolved, an error will be returned.
+    fn join(
+        &mut self,
+        left: usize,
+        right: usize,
+        report: &mut MergeReport,
+        edges: &MergeStrategy,
+        data: &MergeStrategy,
+    ) -> Result<()> {
         for v in self.keys() {
-            let mut nv = self.vertices.get(v).unwrap().clone();
-            for e in &self.vertices.get_mut(v).unwrap().edges {
-                if *e.1 == right {
-                    nv.edges.insert(*e.0, left);
-                }
+            let vtx = self.vertices.get_mut(v).unwrap();
+            let incoming: Vec<Label> = vtx
+                .edges
+                .iter()
+                .filter(|(_, to)| **to == right)
+                .map(|(a, _)| *a)
+                .collect();
+            for a in incoming {
+                vtx.edges.insert(a, left);
             }
-            self.vertices.insert(v, nv);
         }
         let kids = self
             .kids(right)
             .map(|(a, v)| (*a, *v))
             .collect::<Vec<(Label, usize)>>();
-        for e in kids {
-            assert!(
-                self.kid(left, e.0).is_none(),
-                "Can't merge ν{right} into ν{left}, due to conflict in '{}'",
-                e.0
-            );
-            self.bind(left, e.1, e.0);
+        for (a, to) in kids {
+            match self.kid(left, a) {
+                Some(t) if t == to => {}
+                Some(t)
+                    if !edges.prefers_right(&MergeConflict::Edge(
+                        left,
+                        a,
+                        Some(t),
+                        Some(to),
+                    ))? => {}
+                _ => self.bind(left, to, a),
+            }
+        }
+        let vtx = self.vertices.get(right).unwrap();
+        if vtx.persistence != Persistence::Empty {
+            let d = vtx.data.clone();
+            self.put_merged(left, &d, report, data)?;
+        }
+        self.remove(right);
+        report.added.remove(&right);
+        report.joined += 1;
+        for v in report.mapping.values_mut() {
+            if *v == right {
+                *v = left;
+            }
         }
-        self.vertices.remove(right);
+        Ok(())
     }
 }
 
@@ -180,9 +575,9 @@ fn merges_a_loop() {
     assert_eq!(5, g.len());
     assert_eq!(1, g.kid(0, Label::from_str("a").unwrap()).unwrap());
     assert_eq!(2, g.kid(1, Label::from_str("b").unwrap()).unwrap());
-    // assert_eq!(3, g.kid(0, "c").unwrap());
-    // assert_eq!(1, g.kid(3, "d").unwrap());
-    // assert_eq!(5, g.kid(1, "e").unwrap());
+    assert_eq!(3, g.kid(0, Label::from_str("c").unwrap()).unwrap());
+    assert_eq!(1, g.kid(3, Label::from_str("d").unwrap()).unwrap());
+    assert_eq!(5, g.kid(1, Label::from_str("e").unwrap()).unwrap());
 }
 
 #[test]
@@ -262,9 +657,6 @@ fn merges_large_loop() {
     assert_eq!(extra.len(), g.len());
 }
 
-#[cfg(test)]
-use crate::Hex;
-
 #[test]
 fn merges_data() {
     let mut g: Sodg<16> = Sodg::empty(256);
@@ -380,3 +772,280 @@ fn two_big_graphs() {
     g.merge(&extra, 0, 0).unwrap();
     assert_eq!(4, g.len());
 }
+
+#[test]
+fn merges_a_diamond() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.add(2);
+    g.bind(0, 2, Label::from_str("b").unwrap());
+    let mut extra = Sodg::empty(256);
+    extra.add(0);
+    extra.add(1);
+    extra.bind(0, 1, Label::from_str("a").unwrap());
+    extra.bind(0, 1, Label::from_str("b").unwrap());
+    extra.add(2);
+    extra.bind(1, 2, Label::from_str("x").unwrap());
+    extra.put(2, &Hex::from(7));
+    g.merge(&extra, 0, 0).unwrap();
+    assert_eq!(3, g.len());
+    let a = g.kid(0, Label::from_str("a").unwrap()).unwrap();
+    assert_eq!(Some(a), g.kid(0, Label::from_str("b").unwrap()));
+    let x = g.kid(a, Label::from_str("x").unwrap()).unwrap();
+    assert_eq!(Hex::from(7), g.data(x).unwrap());
+    let id = g.next_id();
+    g.add(id);
+}
+
+#[cfg(test)]
+fn conflicting_pair() -> (Sodg<16>, Sodg<16>) {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.add(2);
+    g.bind(0, 2, Label::from_str("b").unwrap());
+    g.add(3);
+    g.bind(1, 3, Label::from_str("x").unwrap());
+    g.add(4);
+    g.bind(2, 4, Label::from_str("x").unwrap());
+    let mut extra = Sodg::empty(256);
+    extra.add(0);
+    extra.add(1);
+    extra.bind(0, 1, Label::from_str("a").unwrap());
+    extra.bind(0, 1, Label::from_str("b").unwrap());
+    (g, extra)
+}
+
+#[test]
+fn fails_on_edge_conflict() {
+    let (mut g, extra) = conflicting_pair();
+    let msg = g.merge(&extra, 0, 0).unwrap_err().to_string();
+    assert!(msg.contains("ν2.x: ν4 vs. ν3"), "{msg}");
+}
+
+#[test]
+fn fails_merge_intact() {
+    let (mut g, extra) = conflicting_pair();
+    let before = g.clone();
+    assert!(g.merge(&extra, 0, 0).is_err());
+    assert_eq!(5, g.len());
+    assert_eq!(before, g);
+}
+
+#[test]
+fn resolves_edge_conflict() {
+    let (mut g, extra) = conflicting_pair();
+    g.merge_with(
+        &extra,
+        0,
+        0,
+        &MergeStrategy::PreferRight,
+        &MergeStrategy::Fail,
+    )
+    .unwrap();
+    let a = g.kid(0, Label::from_str("a").unwrap()).unwrap();
+    assert_eq!(Some(a), g.kid(0, Label::from_str("b").unwrap()));
+    assert_eq!(Some(3), g.kid(a, Label::from_str("x").unwrap()));
+    let (mut g, extra) = conflicting_pair();
+    g.merge_with(
+        &extra,
+        0,
+        0,
+        &MergeStrategy::Custom(Box::new(|c| {
+            Ok(!matches!(c, MergeConflict::Edge(_, _, Some(4), _)))
+        })),
+        &MergeStrategy::Fail,
+    )
+    .unwrap();
+    let a = g.kid(0, Label::from_str("a").unwrap()).unwrap();
+    assert_eq!(Some(4), g.kid(a, Label::from_str("x").unwrap()));
+}
+
+#[test]
+fn fails_on_data_conflict() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(1);
+    g.put(1, &Hex::from(1));
+    let mut extra = Sodg::empty(256);
+    extra.add(1);
+    extra.put(1, &Hex::from(2));
+    let r = g.merge_with(&extra, 1, 1, &MergeStrategy::Fail, &MergeStrategy::Fail);
+    assert!(r
+        .unwrap_err()
+        .to_string()
+        .contains("ν1.Δ: 00-00-00-01 vs. 00-00-00-02"));
+}
+
+#[test]
+fn reports_merge() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.put(1, &Hex::from(1));
+    g.add(2);
+    g.bind(0, 2, Label::from_str("b").unwrap());
+    let mut extra = Sodg::empty(256);
+    extra.add(0);
+    extra.add(7);
+    extra.bind(0, 7, Label::from_str("a").unwrap());
+    extra.bind(0, 7, Label::from_str("b").unwrap());
+    extra.put(7, &Hex::from(2));
+    extra.add(8);
+    extra.bind(7, 8, Label::from_str("x").unwrap());
+    let report = g.merge(&extra, 0, 0).unwrap();
+    assert_eq!(1, report.added());
+    assert_eq!(1, report.joined());
+    assert_eq!(1, report.overwritten());
+    let a = g.kid(0, Label::from_str("a").unwrap()).unwrap();
+    assert_eq!(Some(&a), report.mapping().get(&7));
+    let x = g.kid(a, Label::from_str("x").unwrap()).unwrap();
+    assert_eq!(Some(&x), report.mapping().get(&8));
+}
+
+#[cfg(test)]
+fn three_graphs() -> (Sodg<16>, Sodg<16>, Sodg<16>) {
+    let mut base: Sodg<16> = Sodg::empty(256);
+    base.add(0);
+    base.add(1);
+    base.bind(0, 1, Label::from_str("a").unwrap());
+    base.add(2);
+    base.bind(0, 2, Label::from_str("b").unwrap());
+    base.put(2, &Hex::from(1));
+    let mut ours = base.clone();
+    ours.add(3);
+    ours.bind(0, 3, Label::from_str("a").unwrap());
+    ours.put(2, &Hex::from(2));
+    let mut theirs = base.clone();
+    theirs.add(4);
+    theirs.bind(0, 4, Label::from_str("a").unwrap());
+    theirs.add(5);
+    theirs.bind(1, 5, Label::from_str("c").unwrap());
+    theirs.put(2, &Hex::from(3));
+    (base, ours, theirs)
+}
+
+#[test]
+fn merges_in_three_ways() {
+    let (base, mut ours, theirs) = three_graphs();
+    let conflicts = ours
+        .merge_three_way(
+            &base,
+            &theirs,
+            &MergeStrategy::PreferLeft,
+            &MergeStrategy::PreferRight,
+        )
+        .unwrap();
+    assert_eq!(
+        vec!["ν0.a: ν3 vs. ν4", "ν2.Δ: 00-00-00-02 vs. 00-00-00-03"],
+        conflicts
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+    );
+    assert_eq!(Some(3), ours.kid(0, Label::from_str("a").unwrap()));
+    assert_eq!(Some(5), ours.kid(1, Label::from_str("c").unwrap()));
+    assert_eq!(Hex::from(3), ours.data(2).unwrap());
+}
+
+#[test]
+fn fails_three_way_merge_intact() {
+    let (base, mut ours, theirs) = three_graphs();
+    let before = ours.clone();
+    assert!(ours
+        .merge_three_way(
+            &base,
+            &theirs,
+            &MergeStrategy::PreferLeft,
+            &MergeStrategy::Fail
+        )
+        .is_err());
+    assert_eq!(before, ours);
+}
+
+#[test]
+fn renumbers_vertices_added_in_three_ways() {
+    let mut base: Sodg<16> = Sodg::empty(256);
+    let root = base.next_id();
+    base.add(root);
+    let mut ours = base.clone();
+    let ours_v = ours.next_id();
+    ours.add(ours_v);
+    ours.bind(root, ours_v, Label::from_str("x").unwrap());
+    let mut theirs = base.clone();
+    let theirs_v = theirs.next_id();
+    theirs.add(theirs_v);
+    let theirs_k = theirs.next_id();
+    theirs.add(theirs_k);
+    theirs.bind(theirs_v, theirs_k, Label::from_str("k").unwrap());
+    theirs.bind(root, theirs_v, Label::from_str("y").unwrap());
+    assert_eq!(ours_v, theirs_v);
+    let conflicts = ours
+        .merge_three_way(&base, &theirs, &MergeStrategy::Fail, &MergeStrategy::Fail)
+        .unwrap();
+    assert!(conflicts.is_empty());
+    assert_eq!(4, ours.len());
+    assert_eq!(Some(ours_v), ours.kid(root, Label::from_str("x").unwrap()));
+    assert_eq!(0, ours.kids(ours_v).count());
+    let added = ours.kid(root, Label::from_str("y").unwrap()).unwrap();
+    assert_ne!(ours_v, added);
+    let kid = ours.kid(added, Label::from_str("k").unwrap()).unwrap();
+    assert!(![root, ours_v, added].contains(&kid));
+    assert!(![root, ours_v, added, kid].contains(&ours.next_id()));
+}
+
+#[test]
+fn keeps_vertex_pointed_in_three_ways() {
+    let mut base: Sodg<16> = Sodg::empty(256);
+    base.add(0);
+    base.add(1);
+    base.bind(0, 1, Label::from_str("a").unwrap());
+    let mut ours = base.clone();
+    ours.add(2);
+    ours.bind(0, 2, Label::from_str("b").unwrap());
+    ours.bind(2, 1, Label::from_str("z").unwrap());
+    let mut theirs = base.clone();
+    theirs.unbind(0, Label::from_str("a").unwrap());
+    theirs.remove(1);
+    ours.merge_three_way(&base, &theirs, &MergeStrategy::Fail, &MergeStrategy::Fail)
+        .unwrap();
+    assert_eq!(Some(1), ours.kid(2, Label::from_str("z").unwrap()));
+    assert!(ours.keys().contains(&1));
+    assert!(ours.kid(0, Label::from_str("a").unwrap()).is_none());
+}
+
+#[test]
+fn takes_data_in_three_ways() {
+    let mut base: Sodg<16> = Sodg::empty(256);
+    base.add(0);
+    base.put(0, &Hex::from(1));
+    base.put(0, &Hex::from(1));
+    let mut ours = base.clone();
+    ours.add(1);
+    let mut theirs = base.clone();
+    theirs.data(0);
+    ours.merge_three_way(&base, &theirs, &MergeStrategy::Fail, &MergeStrategy::Fail)
+        .unwrap();
+    let mut expected = theirs.clone();
+    expected.add(1);
+    assert_eq!(expected, ours);
+}
+
+#[test]
+fn reports_unreachable_vertices() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    let mut extra: Sodg<16> = Sodg::empty(256);
+    extra.add(0);
+    extra.add(1);
+    extra.add(2);
+    extra.bind(0, 1, Label::from_str("a").unwrap());
+    let msg = g.merge(&extra, 0, 0).unwrap_err().to_string();
+    assert!(
+        msg.contains("1 of them are not reachable from ν0: ν2"),
+        "{msg}"
+    );
+}
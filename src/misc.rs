@@ -19,11 +19,7 @@ impl<const N: usize> Sodg<N> {
     /// Get keys of all vertices alive?
     #[must_use]
     pub fn keys(&self) -> Vec<usize> {
-        self.vertices
-            .iter()
-            .filter(|(_, vtx)| vtx.branch != 0)
-            .map(|(v, _)| v)
-            .collect::<Vec<usize>>()
+        self.vertices.alive().collect::<Vec<usize>>()
     }
 }
 
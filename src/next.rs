@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
 // SPDX-License-Identifier: MIT
 
-use crate::Sodg;
+use crate::{Entry, Sodg};
 
 impl<const N: usize> Sodg<N> {
     /// Get next unique ID of a vertex.
@@ -14,16 +14,13 @@ impl<const N: usize> Sodg<N> {
     /// May panic if not enough IDs are available.
     #[inline]
     pub fn next_id(&mut self) -> usize {
-        let mut id = self.next_v;
-        id = self
-            .vertices
-            .iter()
-            .find(|(v, vtx)| vtx.branch == 0 && *v >= id)
-            .map(|(v, _)| v)
+        let id = (self.next_v..self.vertices.capacity())
+            .find(|v| !self.vertices.is_alive(*v))
             .unwrap();
         let next = id + 1;
         if next > self.next_v {
             self.next_v = next;
+            self.log(|| Entry::Next(next));
         }
         id
     }
@@ -1,8 +1,8 @@
 // SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
 // SPDX-License-Identifier: MIT
 
-use crate::{Hex, Label};
-use crate::{Persistence, Sodg, BRANCH_NONE, BRANCH_STATIC};
+use crate::{Entry, Hex, Label};
+use crate::{Persistence, Sodg, Vertex, BRANCH_NONE, BRANCH_STATIC};
 use anyhow::Context;
 #[cfg(debug_assertions)]
 use log::trace;
@@ -31,6 +31,7 @@ impl<const N: usize> Sodg<N> {
         self.vertices.get_mut(v1).unwrap().branch = 1;
         #[cfg(debug_assertions)]
         trace!("#add: vertex ν{v1} added");
+        self.log(|| Entry::Add(v1));
     }
 
     /// Make an edge `e1` from vertex `v1` to vertex `v2` and put `a` label on it.
@@ -96,6 +97,7 @@ impl<const N: usize> Sodg<N> {
             v2,
             self.vertices.get(v2).unwrap().branch,
         );
+        self.log(|| Entry::Bind(v1, v2, a));
     }
 
     /// Set vertex data.
@@ -118,11 +120,20 @@ impl<const N: usize> Sodg<N> {
     #[inline]
     pub fn put(&mut self, v: usize, d: &Hex) {
         let vtx = self.vertices.get_mut(v).unwrap();
+        if let Some(index) = &mut self.index {
+            if vtx.persistence != Persistence::Empty {
+                if let Some(all) = index.get_mut(&vtx.data) {
+                    all.remove(&v);
+                }
+            }
+            index.entry(d.clone()).or_default().insert(v);
+        }
         vtx.persistence = Persistence::Stored;
         vtx.data = d.clone();
         *self.stores.get_mut(vtx.branch).unwrap() += 1;
         #[cfg(debug_assertions)]
         trace!("#put: data of ν{v} set to {d}");
+        self.log(|| Entry::Put(v, d.clone()));
     }
 
     /// Read vertex data, and then submit the vertex to garbage collection.
@@ -164,7 +175,13 @@ impl<const N: usize> Sodg<N> {
                 if *s == 0 {
                     let members = self.branches.get_mut(branch).unwrap();
                     for v in members.into_iter() {
-                        self.vertices.get_mut(v).unwrap().branch = BRANCH_NONE;
+                        let vtx = self.vertices.get_mut(v).unwrap();
+                        vtx.branch = BRANCH_NONE;
+                        if let Some(index) = &mut self.index {
+                            if let Some(all) = index.get_mut(&vtx.data) {
+                                all.remove(&v);
+                            }
+                        }
                     }
                     #[cfg(debug_assertions)]
                     trace!(
@@ -181,6 +198,7 @@ impl<const N: usize> Sodg<N> {
                 }
                 #[cfg(debug_assertions)]
                 trace!("#data: data of ν{v} retrieved");
+                self.log(|| Entry::Data(v));
                 Some(d)
             }
             Persistence::Taken => {
@@ -266,6 +284,80 @@ impl<const N: usize> Sodg<N> {
         }
         None
     }
+
+    /// Remove an edge with the label `a` departing from the vertex `v`.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(42);
+    /// let k = Label::from_str("k").unwrap();
+    /// g.bind(0, 42, k);
+    /// g.unbind(0, k);
+    /// assert!(g.kid(0, k).is_none());
+    /// ```
+    ///
+    /// If there is no such edge, nothing will happen. The vertex the
+    /// edge was pointing to stays in the graph.
+    ///
+    /// # Panics
+    ///
+    /// If vertex `v` is absent, it will panic.
+    #[inline]
+    pub fn unbind(&mut self, v: usize, a: Label) {
+        self.vertices.get_mut(v).unwrap().edges.remove(&a);
+        #[cfg(debug_assertions)]
+        trace!("#unbind: edge ν{v}.{a} removed");
+        self.log(|| Entry::Unbind(v, a));
+    }
+
+    /// Remove the vertex `v`, together with its data and all edges
+    /// departing from it.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use sodg::Sodg;
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(42);
+    /// g.remove(42);
+    /// assert_eq!(0, g.len());
+    /// ```
+    ///
+    /// Edges of other vertices, which point to the vertex `v`, are not
+    /// removed: it's up to you to [`Sodg::unbind`] them.
+    ///
+    /// # Panics
+    ///
+    /// If vertex `v` is absent, it will panic.
+    pub fn remove(&mut self, v: usize) {
+        let vtx = self.vertices.get_mut(v).unwrap();
+        let branch = vtx.branch;
+        if branch > BRANCH_STATIC {
+            let members = self.branches.get_mut(branch).unwrap();
+            let rest: Vec<usize> = members.into_iter().filter(|m| *m != v).collect();
+            members.clear();
+            for m in rest {
+                members.push(m);
+            }
+        }
+        if vtx.persistence == Persistence::Stored {
+            *self.stores.get_mut(branch).unwrap() -= 1;
+        }
+        if let Some(index) = &mut self.index {
+            if let Some(all) = index.get_mut(&vtx.data) {
+                all.remove(&v);
+            }
+        }
+        *vtx = Vertex::empty();
+        #[cfg(debug_assertions)]
+        trace!("#remove: vertex ν{v} removed from b{branch}");
+        self.log(|| Entry::Remove(v));
+    }
 }
 
 #[cfg(test)]
@@ -424,3 +516,18 @@ fn adds_twice() {
     g.add(0);
     g.add(0);
 }
+
+#[test]
+fn removes_vertex_from_branch() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(1);
+    g.add(2);
+    g.bind(1, 2, Label::Alpha(0));
+    g.put(2, &Hex::from(42));
+    g.remove(2);
+    assert_eq!(1, g.len());
+    assert_eq!(1, g.branches.get(2).unwrap().len());
+    assert_eq!(&0, g.stores.get(2).unwrap());
+    g.unbind(1, Label::Alpha(0));
+    assert_eq!(0, g.kids(1).count());
+}
@@ -0,0 +1,362 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
+// SPDX-License-Identifier: MIT
+
+use crate::{Change, Hex, Label, Persistence, Sodg, SodgDiff, SodgPatch, Vertex};
+use anyhow::{anyhow, Result};
+use log::debug;
+use std::collections::HashMap;
+
+impl SodgPatch {
+    /// Make a new patch from a list of changes, which will be
+    /// applied in the order provided.
+    #[must_use]
+    pub const fn new(changes: Vec<Change>) -> Self {
+        Self { changes }
+    }
+
+    /// Get all changes, in the order they must be applied.
+    #[must_use]
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+}
+
+impl From<SodgDiff> for SodgPatch {
+    fn from(diff: SodgDiff) -> Self {
+        Self::new(diff.changes().to_vec())
+    }
+}
+
+/// The state of a graph as it is expected to be in the middle of
+/// a patch: changes made so far on top of the original graph.
+struct Overlay<'a, const N: usize> {
+    /// The graph the patch is applied to.
+    g: &'a Sodg<N>,
+    /// Vertices added or removed so far.
+    alive: HashMap<usize, bool>,
+    /// Edges added, re-targeted, or removed so far.
+    edges: HashMap<(usize, Label), Option<usize>>,
+    /// Data changed so far.
+    data: HashMap<usize, Option<Hex>>,
+    /// Numbers of edges departing from vertices, changed so far.
+    degrees: HashMap<usize, usize>,
+}
+
+impl<const N: usize> Overlay<'_, N> {
+    /// Is this vertex alive?
+    fn alive(&self, v: usize) -> bool {
+        v < self.g.vertices.capacity()
+            && self
+                .alive
+                .get(&v)
+                .copied()
+                .unwrap_or_else(|| self.g.vertices.get(v).unwrap().branch != 0)
+    }
+
+    /// Where does this edge point to?
+    fn edge(&self, v: usize, a: Label) -> Option<usize> {
+        self.edges
+            .get(&(v, a))
+            .copied()
+            .unwrap_or_else(|| self.g.kid(v, a))
+    }
+
+    /// What data is in this vertex?
+    fn data(&self, v: usize) -> Option<Hex> {
+        self.data.get(&v).cloned().unwrap_or_else(|| {
+            let vtx = self.g.vertices.get(v).unwrap();
+            if vtx.persistence == Persistence::Empty {
+                None
+            } else {
+                Some(vtx.data.clone())
+            }
+        })
+    }
+
+    /// How many edges depart from this vertex?
+    fn degree(&self, v: usize) -> usize {
+        self.degrees
+            .get(&v)
+            .copied()
+            .unwrap_or_else(|| self.g.vertices.get(v).unwrap().edges.len())
+    }
+
+    /// Check whether the change fits and remember its effect.
+    ///
+    /// # Errors
+    ///
+    /// If the change doesn't fit, an error will be returned explaining why.
+    fn check(&mut self, c: &Change) -> Result<()> {
+        let absent = |v: usize| anyhow!("ν{v} is absent");
+        match c {
+            Change::AddVertex(v) => {
+                if *v >= self.g.vertices.capacity() {
+                    return Err(anyhow!(
+                        "ν{v} is out of capacity of {}",
+                        self.g.vertices.capacity()
+                    ));
+                }
+                if self.alive(*v) {
+                    return Err(anyhow!("ν{v} already exists"));
+                }
+                self.alive.insert(*v, true);
+                self.data.insert(*v, None);
+                self.degrees.insert(*v, 0);
+                self.g
+                    .vertices
+                    .get(*v)
+                    .unwrap()
+                    .edges
+                    .iter()
+                    .for_each(|(a, _)| {
+                        self.edges.insert((*v, *a), None);
+                    });
+            }
+            Change::RemoveVertex(v) => {
+                if !self.alive(*v) {
+                    return Err(absent(*v));
+                }
+                self.alive.insert(*v, false);
+            }
+            Change::AddEdge(v, a, to) | Change::RetargetEdge(v, a, _, to) => {
+                for x in [v, to] {
+                    if !self.alive(*x) {
+                        return Err(absent(*x));
+                    }
+                }
+                let was = if let Change::RetargetEdge(_, _, was, _) = c {
+                    Some(*was)
+                } else {
+                    None
+                };
+                let now = self.edge(*v, *a);
+                if now != was {
+                    return Err(anyhow!(
+                        "ν{v}.{a} is expected to {}, but it {}",
+                        was.map_or_else(|| "be absent".to_string(), |w| format!("point to ν{w}")),
+                        now.map_or_else(|| "is absent".to_string(), |w| format!("points to ν{w}"))
+                    ));
+                }
+                if now.is_none() {
+                    let degree = self.degree(*v);
+                    if degree >= N {
+                        return Err(anyhow!(
+                            "ν{v} already has {degree} edges, while {N} is the maximum"
+                        ));
+                    }
+                    self.degrees.insert(*v, degree + 1);
+                }
+                self.edges.insert((*v, *a), Some(*to));
+            }
+            Change::RemoveEdge(v, a, to) => {
+                if !self.alive(*v) {
+                    return Err(absent(*v));
+                }
+                let now = self.edge(*v, *a);
+                if now != Some(*to) {
+                    return Err(anyhow!(
+                        "ν{v}.{a} is expected to point to ν{to}, but it {}",
+                        now.map_or_else(|| "is absent".to_string(), |w| format!("points to ν{w}"))
+                    ));
+                }
+                self.degrees.insert(*v, self.degree(*v) - 1);
+                self.edges.insert((*v, *a), None);
+            }
+            Change::PutData(v, was, d) => {
+                if !self.alive(*v) {
+                    return Err(absent(*v));
+                }
+                let now = self.data(*v);
+                if now != *was {
+                    let print =
+                        |d: &Option<Hex>| d.as_ref().map_or_else(|| "∅".to_string(), Hex::print);
+                    return Err(anyhow!(
+                        "ν{v}.Δ is expected to be {}, but it is {}",
+                        print(was),
+                        print(&now)
+                    ));
+                }
+                self.data.insert(*v, d.clone());
+            }
+            Change::TakeData(v) => {
+                if !self.alive(*v) {
+                    return Err(absent(*v));
+                }
+                self.data(*v)
+                    .ok_or_else(|| anyhow!("ν{v}.Δ is expected to be present, but it is ∅"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> Sodg<N> {
+    /// Apply all changes from the patch, atomically.
+    ///
+    /// First, all changes are checked against the expectations they carry.
+    /// If at least one of them doesn't fit, nothing is changed in the graph
+    /// and an error is returned, listing all the conflicts found. Otherwise,
+    /// all changes are applied, in the order they are in the patch.
+    /// If the journal is kept by [`Sodg::journal`], a checkpoint is made
+    /// right after that.
+    ///
+    /// # Errors
+    ///
+    /// If the graph doesn't match the expectations of the patch,
+    /// an error will be returned.
+    ///
+    /// # Panics
+    ///
+    /// If the patch refers to a vertex outside of the capacity
+    /// of the graph, it may panic.
+    pub fn apply(&mut self, patch: &SodgPatch) -> Result<()> {
+        self.checkpointed(|sodg| {
+            let mut overlay = Overlay {
+                g: sodg,
+                alive: HashMap::new(),
+                edges: HashMap::new(),
+                data: HashMap::new(),
+                degrees: HashMap::new(),
+            };
+            let mut conflicts = vec![];
+            for (pos, c) in patch.changes.iter().enumerate() {
+                if let Err(e) = overlay.check(c) {
+                    conflicts.push(format!("  #{} {c}: {e}", pos + 1));
+                }
+            }
+            if !conflicts.is_empty() {
+                return Err(anyhow!(
+                    "Can't apply the patch of {} changes, {} conflict(s) found:\n{}",
+                    patch.changes.len(),
+                    conflicts.len(),
+                    conflicts.join("\n")
+                ));
+            }
+            for c in &patch.changes {
+                match c {
+                    Change::AddVertex(v) => {
+                        *sodg.vertices.get_mut(*v).unwrap() = Vertex::empty();
+                        sodg.add(*v);
+                    }
+                    Change::RemoveVertex(v) => sodg.remove(*v),
+                    Change::AddEdge(v, a, to) | Change::RetargetEdge(v, a, _, to) => {
+                        sodg.bind(*v, *to, *a);
+                    }
+                    Change::RemoveEdge(v, a, _) => sodg.unbind(*v, *a),
+                    Change::PutData(v, _, Some(d)) => sodg.put(*v, d),
+                    Change::PutData(v, _, None) => sodg.clear(*v),
+                    Change::TakeData(v) => {
+                        sodg.data(*v);
+                    }
+                }
+            }
+            debug!("Applied all {} changes of the patch", patch.changes.len());
+            Ok(())
+        })
+    }
+
+    /// Remove data from the vertex `v`, if there is any.
+    fn clear(&mut self, v: usize) {
+        let vtx = self.vertices.get_mut(v).unwrap();
+        if vtx.persistence == Persistence::Stored {
+            *self.stores.get_mut(vtx.branch).unwrap() -= 1;
+        }
+        if let Some(index) = &mut self.index {
+            if let Some(all) = index.get_mut(&vtx.data) {
+                all.remove(&v);
+            }
+        }
+        vtx.persistence = Persistence::Empty;
+        vtx.data = Hex::empty();
+    }
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn applies_diff_as_patch() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.put(1, &Hex::from(1));
+    let mut after = g.clone();
+    after.add(2);
+    after.bind(0, 2, Label::from_str("a").unwrap());
+    after.remove(1);
+    after.put(2, &Hex::from(2));
+    let patch = SodgPatch::from(g.diff(&after));
+    g.apply(&patch).unwrap();
+    assert_eq!(after, g);
+}
+
+#[test]
+fn reports_all_conflicts() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    let before = g.clone();
+    let patch = SodgPatch::new(vec![
+        Change::AddVertex(1),
+        Change::RetargetEdge(0, Label::from_str("a").unwrap(), 5, 1),
+        Change::AddVertex(2),
+        Change::PutData(2, Some(Hex::from(1)), Some(Hex::from(2))),
+    ]);
+    let msg = g.apply(&patch).unwrap_err().to_string();
+    assert!(msg.contains("3 conflict(s)"), "{msg}");
+    assert!(msg.contains("#1 +ν1: ν1 already exists"), "{msg}");
+    assert!(
+        msg.contains("ν0.a is expected to point to ν5, but it points to ν1"),
+        "{msg}"
+    );
+    assert!(
+        msg.contains(
+            "#4 ~ν2.Δ 00-00-00-01 ⇒ 00-00-00-02: ν2.Δ is expected to be 00-00-00-01, but it is ∅"
+        ),
+        "{msg}"
+    );
+    assert_eq!(before, g);
+}
+
+#[test]
+fn removes_data_by_patch() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.put(0, &Hex::from(7));
+    g.apply(&SodgPatch::new(vec![Change::PutData(
+        0,
+        Some(Hex::from(7)),
+        None,
+    )]))
+    .unwrap();
+    assert!(g.data(0).is_none());
+}
+
+#[test]
+fn refuses_too_many_edges() {
+    let mut g: Sodg<2> = Sodg::empty(16);
+    for v in 0..4 {
+        g.add(v);
+    }
+    g.bind(0, 1, Label::Alpha(0));
+    let before = g.clone();
+    let patch = SodgPatch::new(vec![
+        Change::AddEdge(0, Label::Alpha(1), 2),
+        Change::AddEdge(0, Label::Alpha(2), 3),
+    ]);
+    let msg = g.apply(&patch).unwrap_err().to_string();
+    assert!(
+        msg.contains("#2 +ν0.α2 ➞ ν3: ν0 already has 2 edges, while 2 is the maximum"),
+        "{msg}"
+    );
+    assert_eq!(before, g);
+    g.apply(&SodgPatch::new(vec![
+        Change::RemoveEdge(0, Label::Alpha(0), 1),
+        Change::AddEdge(0, Label::Alpha(1), 2),
+        Change::AddEdge(0, Label::Alpha(2), 3),
+    ]))
+    .unwrap();
+    assert_eq!(2, g.kids(0).count());
+}
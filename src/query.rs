@@ -0,0 +1,174 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
+// SPDX-License-Identifier: MIT
+
+use crate::{Pattern, Persistence, Sodg};
+use std::collections::HashMap;
+
+impl<const N: usize> Sodg<N> {
+    /// Find all alive vertices that match the pattern.
+    ///
+    /// Every vertex found is returned together with the bindings
+    /// made by [`Pattern::Bind`]. If a vertex matches the pattern
+    /// in many different ways, it is returned many times, once
+    /// per each combination of bindings. The data of vertices is
+    /// read without submitting them to garbage collection, unlike [`Sodg::data`].
+    #[must_use]
+    pub fn query(&self, p: &Pattern) -> Vec<(usize, HashMap<String, usize>)> {
+        let mut found = vec![];
+        for v in self.keys() {
+            for b in self.bindings(v, p) {
+                found.push((v, b));
+            }
+        }
+        found
+    }
+
+    /// Match the vertex `v` against the pattern and return all
+    /// possible bindings, or an empty vector if it doesn't match.
+    fn bindings(&self, v: usize, p: &Pattern) -> Vec<HashMap<String, usize>> {
+        let vtx = self.vertices.get(v).unwrap();
+        let data = if vtx.persistence == Persistence::Empty {
+            None
+        } else {
+            Some(&vtx.data)
+        };
+        let yes = |ok: bool| if ok { vec![HashMap::new()] } else { vec![] };
+        match p {
+            Pattern::Any => yes(true),
+            Pattern::Data => yes(data.is_some()),
+            Pattern::Is(d) => yes(data == Some(d)),
+            Pattern::Size(s) => yes(data.is_some_and(|d| d.len() == *s)),
+            Pattern::Edge(a, sub) => self
+                .kid(v, *a)
+                .map_or_else(Vec::new, |to| self.bindings(to, sub)),
+            Pattern::Kid(sub) => self
+                .sorted_kids(v)
+                .into_iter()
+                .rev()
+                .flat_map(|(_, to)| self.bindings(to, sub))
+                .collect(),
+            Pattern::All(subs) => {
+                let mut all = vec![HashMap::new()];
+                for sub in subs {
+                    let more = self.bindings(v, sub);
+                    all = all
+                        .iter()
+                        .flat_map(|before| {
+                            more.iter()
+                                .filter_map(|after| Self::join_bindings(before, after))
+                        })
+                        .collect();
+                    if all.is_empty() {
+                        break;
+                    }
+                }
+                all
+            }
+            Pattern::Either(subs) => subs.iter().flat_map(|sub| self.bindings(v, sub)).collect(),
+            Pattern::Not(sub) => yes(self.bindings(v, sub).is_empty()),
+            Pattern::Bind(name, sub) => {
+                let mut all = self.bindings(v, sub);
+                all.retain(|b| b.get(name).is_none_or(|w| *w == v));
+                for b in &mut all {
+                    b.insert(name.clone(), v);
+                }
+                all
+            }
+        }
+    }
+
+    /// Join two sets of bindings, if they don't contradict each other.
+    fn join_bindings(
+        left: &HashMap<String, usize>,
+        right: &HashMap<String, usize>,
+    ) -> Option<HashMap<String, usize>> {
+        let mut joined = left.clone();
+        for (name, v) in right {
+            if *joined.entry(name.clone()).or_insert(*v) != *v {
+                return None;
+            }
+        }
+        Some(joined)
+    }
+}
+
+#[cfg(test)]
+use crate::{Hex, Label};
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn finds_by_data_size() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::Greek('Δ'));
+    g.put(1, &Hex::from(42_i64));
+    g.add(2);
+    g.add(3);
+    g.bind(2, 3, Label::Greek('Δ'));
+    g.put(3, &Hex::from_str_bytes("hi"));
+    let found = g.query(&Pattern::Edge(
+        Label::Greek('Δ'),
+        Box::new(Pattern::Size(8)),
+    ));
+    assert_eq!(
+        vec![0],
+        found.iter().map(|(v, _)| *v).collect::<Vec<usize>>()
+    );
+}
+
+#[test]
+fn doesnt_take_data() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.put(0, &Hex::from_str_bytes("hello"));
+    assert_eq!(1, g.query(&Pattern::Is(Hex::from_str_bytes("hello"))).len());
+    assert_eq!(1, g.len());
+    assert!(g.vertices.get(0).unwrap().persistence == Persistence::Stored);
+}
+
+#[test]
+fn binds_consistently() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.add(2);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.bind(0, 1, Label::from_str("b").unwrap());
+    g.bind(0, 2, Label::from_str("c").unwrap());
+    let same = |x: &str, y: &str| {
+        Pattern::All(vec![
+            Pattern::Edge(
+                Label::from_str(x).unwrap(),
+                Box::new(Pattern::Bind("t".to_string(), Box::new(Pattern::Any))),
+            ),
+            Pattern::Edge(
+                Label::from_str(y).unwrap(),
+                Box::new(Pattern::Bind("t".to_string(), Box::new(Pattern::Any))),
+            ),
+        ])
+    };
+    assert_eq!(1, g.query(&same("a", "b")).len());
+    assert_eq!(0, g.query(&same("a", "c")).len());
+}
+
+#[test]
+fn finds_leaves() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::Alpha(0));
+    g.add(2);
+    g.bind(0, 2, Label::Alpha(1));
+    let leaves = g.query(&Pattern::Not(Box::new(Pattern::Kid(Box::new(
+        Pattern::Any,
+    )))));
+    assert_eq!(
+        vec![1, 2],
+        leaves.iter().map(|(v, _)| *v).collect::<Vec<usize>>()
+    );
+    let parents = g.query(&Pattern::Kid(Box::new(Pattern::Any)));
+    assert_eq!(2, parents.len());
+}
@@ -14,8 +14,10 @@ impl Script {
     /// Make a new one, parsing a string with instructions.
     ///
     /// Instructions
-    /// must be separated by semicolon. There are just three of them
-    /// possible: `ADD`, `BIND`, and `PUT`. The arguments must be
+    /// must be separated by semicolon. There are just six of them
+    /// possible: `ADD`, `BIND`, `PUT`, `UNBIND`, `DROP`, and `DATA`, which
+    /// reads the data of a vertex, just like [`Sodg::data`] does, thus
+    /// submitting it to garbage collection. The arguments must be
     /// separated by a comma. An argument may either be 1) a positive integer
     /// (possibly prepended by `ν`),
     /// 2) a variable started with `$`, 3) an attribute name, or
@@ -105,6 +107,20 @@ impl Script {
                 let d = Self::parse_data(args.get(1).with_context(|| "Data is expected")?)?;
                 g.put(v, &d);
             }
+            "UNBIND" => {
+                let v = self.parse(args.first().with_context(|| "V is expected")?, g)?;
+                let a =
+                    Label::from_str(args.get(1).with_context(|| "Label is expected")?.as_str())?;
+                g.unbind(v, a);
+            }
+            "DROP" => {
+                let v = self.parse(args.first().with_context(|| "V is expected")?, g)?;
+                g.remove(v);
+            }
+            "DATA" => {
+                let v = self.parse(args.first().with_context(|| "V is expected")?, g)?;
+                g.data(v);
+            }
             cmd => {
                 return Err(anyhow!("Unknown command: {cmd}"));
             }
@@ -121,6 +137,9 @@ impl Script {
         static DATA_STRIP: Lazy<Regex> = Lazy::new(|| Regex::new("[ \t\n\r\\-]").unwrap());
         static DATA: Lazy<Regex> =
             Lazy::new(|| Regex::new("^[0-9A-Fa-f]{2}([0-9A-Fa-f]{2})*$").unwrap());
+        if s.trim() == "--" {
+            return Ok(Hex::empty());
+        }
         let d: &str = &DATA_STRIP.replace_all(s, "");
         if DATA.is_match(d) {
             let bytes: Vec<u8> = (0..d.len())
@@ -176,3 +195,23 @@ fn simple_command() {
     assert_eq!("привет", g.data(1).unwrap().to_utf8().unwrap());
     assert_eq!(1, g.kid(0, Label::from_str("foo").unwrap()).unwrap());
 }
+
+#[test]
+fn unbinds_and_drops() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    let mut s = Script::from_str(
+        "ADD(0); ADD(1); BIND(0, 1, foo); PUT(1, --);
+        UNBIND(0, foo); DROP(1);",
+    );
+    assert_eq!(6, s.deploy_to(&mut g).unwrap());
+    assert_eq!(1, g.len());
+    assert!(g.kid(0, Label::from_str("foo").unwrap()).is_none());
+}
+
+#[test]
+fn reads_data() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    let mut s = Script::from_str("ADD(0); ADD(1); BIND(0, 1, foo); PUT(1, 2A); DATA(1);");
+    assert_eq!(5, s.deploy_to(&mut g).unwrap());
+    assert_eq!(0, g.len());
+}
@@ -1,74 +1,749 @@
 // SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
 // SPDX-License-Identifier: MIT
 
-use crate::Sodg;
-use anyhow::{Context, Result};
-use bincode::{deserialize, serialize};
+use crate::{Hex, Label, Persistence, Sodg, Vertex};
+use anyhow::{anyhow, Context, Result};
+use bincode::{deserialize_from, serialize_into};
+use itertools::Itertools;
 use log::trace;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Cursor, Read, Write};
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Instant;
 
+/// The first bytes of every file made by [`Sodg::save`].
+const MAGIC: &[u8; 4] = b"SODG";
+
+/// The version of the format of files made by [`Sodg::save`].
+///
+/// Files without the [`MAGIC`] in front of them are of the version zero:
+/// they contain nothing but the graph, serialized entirely. In the files
+/// of the version one, the [`Header`] goes first, followed by its checksum,
+/// then the body, see [`Sodg::write_body`], and then the trailer
+/// with the checksum and the size of the body.
+const VERSION: u16 = 1;
+
+/// The flag in the [`Header`], which means that the body is compressed
+/// with DEFLATE, while the trailer is about the body before compression.
+const FLAG_DEFLATE: u16 = 1;
+
+/// The header of a file made by [`Sodg::save`], which goes after
+/// the [`MAGIC`] and the [`VERSION`], right before the body.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    /// Flags of the format, like [`FLAG_DEFLATE`].
+    flags: u16,
+    /// The maximum number of edges departing from a vertex, the `N` of [`Sodg`].
+    arity: u32,
+    /// The total number of vertices the graph may have.
+    capacity: u64,
+    /// All labels of edges found in the graph, sorted.
+    labels: Vec<Label>,
+}
+
+/// A stream, which calculates the checksum and the size
+/// of all bytes going through it.
+struct Tracked<T> {
+    /// The stream.
+    inner: T,
+    /// The checksum of the bytes seen so far.
+    hasher: crc32fast::Hasher,
+    /// The number of bytes seen so far.
+    size: u64,
+}
+
+impl<T> Tracked<T> {
+    /// Start tracking the stream.
+    fn new(inner: T) -> Self {
+        Self {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+            size: 0,
+        }
+    }
+
+    /// Remember the bytes just seen.
+    fn seen(&mut self, bytes: &[u8]) {
+        self.hasher.update(bytes);
+        self.size += bytes.len() as u64;
+    }
+
+    /// Get the checksum of the bytes seen so far.
+    fn checksum(&self) -> u32 {
+        self.hasher.clone().finalize()
+    }
+}
+
+impl<W: Write> Write for Tracked<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.seen(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<R: Read> Read for Tracked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.seen(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// A stream, which writes bytes in frames, each prefixed with its size,
+/// so that the reader knows where they end, without reading further.
+#[cfg(feature = "compression")]
+struct Framed<T> {
+    /// The stream.
+    inner: T,
+}
+
+#[cfg(feature = "compression")]
+impl<W: Write> Framed<W> {
+    /// Write the empty frame, which marks the end of all frames.
+    fn close(&mut self) -> std::io::Result<()> {
+        self.inner.write_all(&0_u32.to_le_bytes())
+    }
+}
+
+#[cfg(feature = "compression")]
+impl<W: Write> Write for Framed<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = u32::try_from(buf.len()).unwrap_or(u32::MAX);
+        if len == 0 {
+            return Ok(0);
+        }
+        self.inner.write_all(&len.to_le_bytes())?;
+        self.inner.write_all(&buf[..len as usize])?;
+        Ok(len as usize)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A stream, which reads frames written by [`Framed`], one by one,
+/// and never reads a byte after the empty frame.
+#[cfg(feature = "compression")]
+struct Unframed<T> {
+    /// The stream.
+    inner: T,
+    /// The number of bytes left in the current frame.
+    left: usize,
+    /// Whether the empty frame is already seen.
+    over: bool,
+}
+
+#[cfg(feature = "compression")]
+impl<T> Unframed<T> {
+    /// Start reading frames from the stream.
+    const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            left: 0,
+            over: false,
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+impl<R: Read> Read for Unframed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() || self.over {
+            return Ok(0);
+        }
+        if self.left == 0 {
+            let mut len = [0; 4];
+            self.inner.read_exact(&mut len)?;
+            self.left = u32::from_le_bytes(len) as usize;
+            if self.left == 0 {
+                self.over = true;
+                return Ok(0);
+            }
+        }
+        let max = buf.len().min(self.left);
+        let n = self.inner.read(&mut buf[..max])?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        self.left -= n;
+        Ok(n)
+    }
+}
+
 impl<const N: usize> Sodg<N> {
     /// Save the entire [`Sodg`] into a binary file.
     ///
-    /// The entire [`Sodg`] can be restored from the file.
-    /// The function returns the size of the file just saved. In order
-    /// to restore from the file, use [`Sodg::load`].
+    /// The entire [`Sodg`] can be restored from the file, including
+    /// the state of [`Sodg::next_id`]. The function returns the size
+    /// of the file just saved. In order to restore from the file, use [`Sodg::load`].
+    ///
+    /// The file is never half-written: the graph is saved into a temporary
+    /// file in the same directory, which replaces the file only
+    /// when it is entirely written and synced to the disk.
     ///
     /// # Errors
     ///
-    /// If impossible to save, an error will be returned.
+    /// If impossible to save, an error will be returned, while the file,
+    /// if it existed, stays as it was.
     pub fn save(&self, path: &Path) -> Result<usize> {
+        self.save_file(path, false)
+    }
+
+    /// Save the entire [`Sodg`] into a binary file, just like [`Sodg::save`]
+    /// does, but compressing the graph with DEFLATE.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use sodg::{Hex, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.put(0, &Hex::from_str_bytes("hello"));
+    /// let tmp = tempfile::TempDir::new().unwrap();
+    /// let file = tmp.path().join("foo.sodg");
+    /// g.save_compressed(&file).unwrap();
+    /// assert_eq!(g, Sodg::<16>::load(&file).unwrap());
+    /// ```
+    ///
+    /// The compression is marked in the header of the file, thus
+    /// [`Sodg::load`] decompresses it, if the library is compiled
+    /// with the `compression` feature.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to save, an error will be returned, while the file,
+    /// if it existed, stays as it was.
+    #[cfg(feature = "compression")]
+    pub fn save_compressed(&self, path: &Path) -> Result<usize> {
+        self.save_file(path, true)
+    }
+
+    /// Save the entire [`Sodg`] into a binary file, compressed or not.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to save, an error will be returned.
+    fn save_file(&self, path: &Path, compress: bool) -> Result<usize> {
         let start = Instant::now();
-        let bytes: Vec<u8> = serialize(self).with_context(|| "Failed to serialize")?;
-        let size = bytes.len();
-        fs::write(path, bytes).with_context(|| format!("Can't write to {}", path.display()))?;
+        let (size, raw) = write_atomically(path, |w| self.write_to(w, compress))?;
         trace!(
-            "Serialized {} vertices ({} bytes) to {} in {:?}",
+            "Serialized {} vertices ({} bytes, {:.2}x compression) to {} in {:?}",
             self.len(),
             size,
+            ratio(raw, size),
             path.display(),
             start.elapsed()
         );
         Ok(size)
     }
 
+    /// Save the entire [`Sodg`] into a stream, in the same format
+    /// as [`Sodg::save`] does, and return the number of bytes written.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use sodg::Sodg;
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// let mut buf = vec![];
+    /// g.save_to(&mut buf).unwrap();
+    /// let after : Sodg<16> = Sodg::load_from(buf.as_slice()).unwrap();
+    /// assert_eq!(g, after);
+    /// ```
+    ///
+    /// The stream starts with a header, which contains the version
+    /// of the format, the `N`, the capacity of the graph, and all labels
+    /// used in it, followed by its checksum. Then, the graph goes, written to the stream
+    /// as it is serialized, without buffering. At the end, the checksum and
+    /// the size of the graph go.
+    ///
+    /// The graph is not compressed, use [`Sodg::save_compressed_to`]
+    /// if you need it to be.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to save, an error will be returned.
+    pub fn save_to<W: Write>(&self, w: W) -> Result<usize> {
+        Ok(self.write_to(w, false)?.0)
+    }
+
+    /// Save the entire [`Sodg`] into a stream, just like [`Sodg::save_to`]
+    /// does, but compressing the graph with DEFLATE.
+    ///
+    /// The compression is marked in the header, thus [`Sodg::load_from`]
+    /// decompresses the graph, if the library is compiled with
+    /// the `compression` feature. The compressed graph is written in frames,
+    /// each prefixed with its size, so that [`Sodg::load_from`] doesn't
+    /// read a byte after the end of the stream.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to save, an error will be returned.
+    #[cfg(feature = "compression")]
+    pub fn save_compressed_to<W: Write>(&self, w: W) -> Result<usize> {
+        Ok(self.write_to(w, true)?.0)
+    }
+
+    /// Write the entire [`Sodg`] into a stream, compressed or not, returning
+    /// the number of bytes written and the size of the body before compression.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to write, or if the compression is asked for
+    /// without the `compression` feature, an error will be returned.
+    fn write_to<W: Write>(&self, w: W, compress: bool) -> Result<(usize, u64)> {
+        let mut out = Tracked::new(w);
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        let header = Header {
+            flags: if compress { FLAG_DEFLATE } else { 0 },
+            arity: u32::try_from(N)?,
+            capacity: self.vertices.capacity() as u64,
+            labels: self.labels(),
+        };
+        let mut head = Tracked::new(&mut out);
+        serialize_into(&mut head, &header).with_context(|| "Failed to serialize the header")?;
+        let checksum = head.checksum();
+        out.write_all(&checksum.to_le_bytes())?;
+        let (checksum, size) = if compress {
+            #[cfg(feature = "compression")]
+            {
+                let mut body = Tracked::new(flate2::write::DeflateEncoder::new(
+                    Framed { inner: &mut out },
+                    flate2::Compression::default(),
+                ));
+                self.write_body(&mut body, &header.labels)
+                    .with_context(|| "Failed to serialize")?;
+                body.inner
+                    .try_finish()
+                    .with_context(|| "Failed to compress")?;
+                body.inner.get_mut().close()?;
+                (body.checksum(), body.size)
+            }
+            #[cfg(not(feature = "compression"))]
+            {
+                return Err(anyhow!(
+                    "The body can't be compressed without the 'compression' feature"
+                ));
+            }
+        } else {
+            let mut body = Tracked::new(&mut out);
+            self.write_body(&mut body, &header.labels)
+                .with_context(|| "Failed to serialize")?;
+            (body.checksum(), body.size)
+        };
+        out.write_all(&checksum.to_le_bytes())?;
+        out.write_all(&size.to_le_bytes())?;
+        Ok((usize::try_from(out.size)?, size))
+    }
+
     /// Load the entire [`Sodg`] from a binary file previously
     /// created by [`Sodg::save`].
     ///
+    /// Files made by older versions of this library, without a header,
+    /// are loaded too.
+    ///
     /// # Errors
     ///
-    /// If impossible to load, an error will be returned.
+    /// If impossible to load, an error will be returned. For example, if
+    /// the file was made by a newer version of the library, if it was
+    /// saved from a [`Sodg`] with a different `N`, or if it is corrupted
+    /// or truncated, which is detected by the checksum and the size of
+    /// the body, written after it.
     pub fn load(path: &Path) -> Result<Self> {
         let start = Instant::now();
-        let bytes =
-            fs::read(path).with_context(|| format!("Can't read from {}", path.display()))?;
-        let size = bytes.len();
-        let sodg: Self = deserialize(&bytes)
+        let file =
+            File::open(path).with_context(|| format!("Can't read from {}", path.display()))?;
+        let size = file.metadata()?.len();
+        let (sodg, raw) = Self::read_from(file)
             .with_context(|| format!("Can't deserialize from {}", path.display()))?;
         trace!(
-            "Deserialized {} vertices ({} bytes) from {} in {:?}",
+            "Deserialized {} vertices ({} bytes, {:.2}x compression) from {} in {:?}",
             sodg.len(),
             size,
+            ratio(raw, usize::try_from(size)?),
             path.display(),
             start.elapsed()
         );
         Ok(sodg)
     }
+
+    /// Load the entire [`Sodg`] from a stream, previously
+    /// written by [`Sodg::save_to`] or [`Sodg::save`].
+    ///
+    /// The header is checked, just like [`Sodg::load`] does. The graph
+    /// is read from the stream as it is deserialized, without buffering,
+    /// and not a byte after its end is read. Thus, a few graphs may be
+    /// loaded from the same stream, one after another.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to load, an error will be returned. For example, if
+    /// the graph is compressed, while the library is compiled
+    /// without the `compression` feature.
+    pub fn load_from<R: Read>(r: R) -> Result<Self> {
+        Ok(Self::read_from(r)?.0)
+    }
+
+    /// Read the entire [`Sodg`] from a stream, returning it together
+    /// with the size of its body before compression.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to read, an error will be returned.
+    fn read_from<R: Read>(mut r: R) -> Result<(Self, u64)> {
+        let mut prefix = Vec::with_capacity(MAGIC.len());
+        r.by_ref()
+            .take(MAGIC.len() as u64)
+            .read_to_end(&mut prefix)?;
+        if prefix != MAGIC {
+            let mut sodg: Self =
+                deserialize_from(Cursor::new(prefix).chain(&mut r)).with_context(|| {
+                    "There is no SODG header and the legacy format is not recognized"
+                })?;
+            sodg.guess_next_v();
+            return Ok((sodg, 0));
+        }
+        let mut version = [0; 2];
+        r.read_exact(&mut version)
+            .with_context(|| "The file is too short, there is no version")?;
+        let version = u16::from_le_bytes(version);
+        if version != VERSION {
+            return Err(anyhow!(
+                "The format version {version} is not supported, only {VERSION} is known"
+            ));
+        }
+        let header = Self::read_header(&mut r)?;
+        let Header {
+            flags,
+            capacity,
+            labels,
+            ..
+        } = header;
+        let (sodg, actual, size) = if flags & FLAG_DEFLATE != 0 {
+            #[cfg(feature = "compression")]
+            {
+                let mut frames = Unframed::new(&mut r);
+                let found = Self::read_tracked(
+                    flate2::read::DeflateDecoder::new(&mut frames),
+                    &labels,
+                    capacity,
+                    true,
+                )?;
+                std::io::copy(&mut frames, &mut std::io::sink())
+                    .with_context(|| "Can't read the body, the file may be corrupted")?;
+                found
+            }
+            #[cfg(not(feature = "compression"))]
+            {
+                return Err(anyhow!(
+                    "The body is compressed, but the library is compiled without the 'compression' feature"
+                ));
+            }
+        } else {
+            Self::read_tracked(&mut r, &labels, capacity, false)?
+        };
+        let mut trailer = [0; 12];
+        r.read_exact(&mut trailer)
+            .with_context(|| "There is no trailer after the body, the file is truncated")?;
+        let (c, s) = trailer.split_at(4);
+        let (expected, declared) = (
+            u32::from_le_bytes(c.try_into()?),
+            u64::from_le_bytes(s.try_into()?),
+        );
+        if size != declared {
+            return Err(anyhow!(
+                "The body is {size} bytes long, while {declared} bytes are expected, the file is corrupted"
+            ));
+        }
+        if actual != expected {
+            return Err(anyhow!(
+                "The checksum of the body is {actual:08X}, while {expected:08X} is expected, the file is corrupted"
+            ));
+        }
+        if sodg.vertices.capacity() as u64 != capacity {
+            return Err(anyhow!(
+                "The capacity of the graph is {}, while {capacity} is declared in the header",
+                sodg.vertices.capacity()
+            ));
+        }
+        Ok((sodg, size))
+    }
+
+    /// Read the body by [`Sodg::read_body`], returning the graph together
+    /// with the checksum and the size of the body. If `drain` is set,
+    /// the stream is read to its end, after the body.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to read, an error will be returned.
+    fn read_tracked<R: Read>(
+        r: R,
+        labels: &[Label],
+        capacity: u64,
+        drain: bool,
+    ) -> Result<(Self, u32, u64)> {
+        let mut body = Tracked::new(r);
+        let sodg = Self::read_body(&mut body, labels, usize::try_from(capacity)?)
+            .with_context(|| "Can't read the body, the file may be corrupted")?;
+        if drain {
+            std::io::copy(&mut body, &mut std::io::sink())
+                .with_context(|| "Can't decompress the body, the file may be corrupted")?;
+        }
+        Ok((sodg, body.checksum(), body.size))
+    }
+
+    /// Read the header, which goes after the [`VERSION`], together with
+    /// its checksum, and check whether the graph may be loaded.
+    ///
+    /// The capacity is checked by reserving memory for all vertices, which
+    /// is released right away, so that a broken capacity doesn't crash
+    /// the process on allocation.
+    ///
+    /// # Errors
+    ///
+    /// If the header is broken or doesn't fit, an error will be returned.
+    fn read_header<R: Read>(mut r: R) -> Result<Header> {
+        let mut head = Tracked::new(&mut r);
+        let header: Header =
+            deserialize_from(&mut head).with_context(|| "Can't read the header")?;
+        let actual = head.checksum();
+        let mut expected = [0; 4];
+        r.read_exact(&mut expected)
+            .with_context(|| "There is no checksum after the header, the file is truncated")?;
+        let expected = u32::from_le_bytes(expected);
+        if actual != expected {
+            return Err(anyhow!(
+                "The checksum of the header is {actual:08X}, while {expected:08X} is expected, the file is corrupted"
+            ));
+        }
+        if header.flags & !FLAG_DEFLATE != 0 {
+            return Err(anyhow!(
+                "The flags {:016b} of the header are not supported",
+                header.flags
+            ));
+        }
+        let arity = header.arity;
+        if arity != u32::try_from(N)? {
+            return Err(anyhow!(
+                "The file contains Sodg<{arity}>, while Sodg<{N}> is expected"
+            ));
+        }
+        let capacity = header.capacity;
+        Vec::<Vertex<N>>::new()
+            .try_reserve_exact(usize::try_from(capacity)?)
+            .with_context(|| format!("The capacity {capacity} of the header is too big"))?;
+        Ok(header)
+    }
+
+    /// Write the body of the file: the state of [`Sodg::next_id`], the branches,
+    /// and then all alive vertices, one by one, each with its ID, branch, data,
+    /// and edges, where labels are referred to by their positions in `labels`.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to write, an error will be returned.
+    fn write_body<W: Write>(&self, mut w: W, labels: &[Label]) -> Result<()> {
+        let positions: HashMap<Label, u32> = labels
+            .iter()
+            .enumerate()
+            .map(|(i, a)| Ok((*a, u32::try_from(i)?)))
+            .collect::<Result<_>>()?;
+        let alive = self.keys();
+        serialize_into(&mut w, &(self.next_v as u64))?;
+        serialize_into(&mut w, &self.stores)?;
+        serialize_into(&mut w, &self.branches)?;
+        serialize_into(&mut w, &(alive.len() as u64))?;
+        for v in alive {
+            let vtx = self.vertices.peek(v);
+            let edges: Vec<(u32, u64)> = vtx
+                .edges
+                .iter()
+                .sorted()
+                .map(|(a, to)| (positions[a], *to as u64))
+                .collect();
+            serialize_into(
+                &mut w,
+                &(
+                    v as u64,
+                    vtx.branch as u64,
+                    &vtx.persistence,
+                    &vtx.data,
+                    edges,
+                ),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Read the body of the file, written by [`Sodg::write_body`], into
+    /// a new graph with the given `capacity`.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to read, an error will be returned.
+    fn read_body<R: Read>(mut r: R, labels: &[Label], capacity: usize) -> Result<Self> {
+        let mut g = Self::empty(capacity);
+        let next: u64 = deserialize_from(&mut r)?;
+        g.next_v = usize::try_from(next)?;
+        g.stores = deserialize_from(&mut r)?;
+        g.branches = deserialize_from(&mut r)?;
+        let total: u64 = deserialize_from(&mut r)?;
+        for _ in 0..total {
+            let (v, branch, persistence, data, edges): (
+                u64,
+                u64,
+                Persistence,
+                Hex,
+                Vec<(u32, u64)>,
+            ) = deserialize_from(&mut r)?;
+            let v = usize::try_from(v)?;
+            if v >= capacity {
+                return Err(anyhow!("The vertex ν{v} is out of capacity of {capacity}"));
+            }
+            if edges.len() > N {
+                return Err(anyhow!(
+                    "The vertex ν{v} has {} edges, while {N} is the maximum",
+                    edges.len()
+                ));
+            }
+            let vtx = g.vertices.get_mut(v).unwrap();
+            vtx.branch = usize::try_from(branch)?;
+            vtx.persistence = persistence;
+            vtx.data = data;
+            for (a, to) in edges {
+                let label = labels
+                    .get(usize::try_from(a)?)
+                    .ok_or_else(|| anyhow!("There is no label no.{a} in the header"))?;
+                vtx.edges.insert(*label, usize::try_from(to)?);
+            }
+        }
+        Ok(g)
+    }
+
+    /// Find all labels of edges of alive vertices, sorted.
+    pub(crate) fn labels(&self) -> Vec<Label> {
+        self.vertices
+            .alive()
+            .flat_map(|v| {
+                self.vertices
+                    .peek(v)
+                    .edges
+                    .iter()
+                    .map(|(a, _)| *a)
+                    .collect::<Vec<Label>>()
+            })
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// Set the state of [`Sodg::next_id`] for a graph loaded from a file
+    /// of an older format, where it was not saved: the next ID goes right
+    /// after the largest ID of alive vertices.
+    fn guess_next_v(&mut self) {
+        self.next_v = self.keys().into_iter().max().map_or(0, |v| v + 1);
+    }
+}
+
+/// The number of temporary files made by [`write_atomically`] so far,
+/// which makes their names unique inside the process.
+static TEMPORARIES: AtomicUsize = AtomicUsize::new(0);
+
+/// Write a file atomically: first, `write` writes into a temporary file
+/// in the same directory, which is then synced to the disk and renamed.
+///
+/// The name of the temporary file contains the ID of the process and
+/// a number unique inside it, so that a few processes or threads may
+/// write the same file at the same time, while the last rename wins.
+///
+/// # Errors
+///
+/// If impossible to write, an error will be returned and
+/// the temporary file will be deleted.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) fn write_atomically<T>(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<T>,
+) -> Result<T> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("There is no file name in {}", path.display()))?;
+    let tmp = path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id(),
+        TEMPORARIES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let done = (|| {
+        let file =
+            File::create(&tmp).with_context(|| format!("Can't write to {}", tmp.display()))?;
+        let mut writer = BufWriter::new(file);
+        let ret =
+            write(&mut writer).with_context(|| format!("Can't write to {}", tmp.display()))?;
+        writer
+            .into_inner()
+            .map_err(std::io::IntoInnerError::into_error)
+            .and_then(|f| f.sync_all())
+            .with_context(|| format!("Can't sync {}", tmp.display()))?;
+        fs::rename(&tmp, path)
+            .with_context(|| format!("Can't rename {} to {}", tmp.display(), path.display()))?;
+        Ok(ret)
+    })();
+    if done.is_err() {
+        let _ = fs::remove_file(&tmp);
+        return done;
+    }
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        File::open(dir)
+            .and_then(|d| d.sync_all())
+            .with_context(|| format!("Can't sync {}", dir.display()))?;
+    }
+    done
+}
+
+/// How many times the body got smaller, being compressed into
+/// the file of `size` bytes, from its `raw` size.
+#[allow(clippy::cast_precision_loss)]
+fn ratio(raw: u64, size: usize) -> f64 {
+    if size == 0 {
+        1.0
+    } else {
+        raw as f64 / size as f64
+    }
 }
 
 #[cfg(test)]
 use tempfile::TempDir;
 
 #[cfg(test)]
-use crate::Hex;
+use bincode::serialize;
 
 #[cfg(test)]
-use crate::Label;
+use std::str::FromStr;
 
+/// Make the beginning of a stream with the header and its checksum.
 #[cfg(test)]
-use std::str::FromStr;
+fn headed(header: &Header) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend(VERSION.to_le_bytes());
+    let head = serialize(header).unwrap();
+    bytes.extend(&head);
+    bytes.extend(crc32fast::hash(&head).to_le_bytes());
+    bytes
+}
 
 #[test]
 fn can_save() {
@@ -92,4 +767,300 @@ fn saves_and_loads() {
     g.save(file.as_path()).unwrap();
     let after: Sodg<1> = Sodg::load(file.as_path()).unwrap();
     assert_eq!(g.inspect(0).unwrap(), after.inspect(0).unwrap());
+    assert_eq!(g, after);
+}
+
+#[test]
+fn refuses_wrong_arity() {
+    let mut g: Sodg<16> = Sodg::empty(100);
+    g.add(0);
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodg");
+    g.save(file.as_path()).unwrap();
+    let msg = format!("{:#}", Sodg::<1>::load(file.as_path()).unwrap_err());
+    assert!(msg.contains("Sodg<16>, while Sodg<1> is expected"), "{msg}");
+}
+
+#[test]
+fn detects_corruption() {
+    let mut g: Sodg<16> = Sodg::empty(100);
+    g.add(0);
+    g.put(0, &Hex::from_str_bytes("hello"));
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodg");
+    g.save(file.as_path()).unwrap();
+    let mut bytes = fs::read(file.as_path()).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xFF;
+    fs::write(file.as_path(), bytes).unwrap();
+    let msg = format!("{:#}", Sodg::<16>::load(file.as_path()).unwrap_err());
+    assert!(msg.contains("corrupted"), "{msg}");
+}
+
+#[test]
+fn refuses_newer_version() {
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodg");
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend((VERSION + 1).to_le_bytes());
+    fs::write(file.as_path(), bytes).unwrap();
+    let msg = format!("{:#}", Sodg::<16>::load(file.as_path()).unwrap_err());
+    assert!(msg.contains("is not supported"), "{msg}");
+}
+
+#[test]
+fn loads_legacy_format() {
+    let mut g: Sodg<16> = Sodg::empty(100);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("foo").unwrap());
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodg");
+    fs::write(file.as_path(), serialize(&g).unwrap()).unwrap();
+    assert_eq!(g, Sodg::<16>::load(file.as_path()).unwrap());
+}
+
+#[test]
+fn keeps_next_id() {
+    let mut g: Sodg<16> = Sodg::empty(100);
+    for _ in 0..5 {
+        let v = g.next_id();
+        g.add(v);
+    }
+    g.remove(4);
+    g.remove(2);
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodg");
+    g.save(file.as_path()).unwrap();
+    let mut after: Sodg<16> = Sodg::load(file.as_path()).unwrap();
+    assert_eq!(g.next_id(), after.next_id());
+    assert_eq!(6, after.next_id());
+}
+
+#[test]
+fn saves_to_stream() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("foo").unwrap());
+    g.put(1, &Hex::from(42));
+    let mut buf = vec![];
+    let size = g.save_to(&mut buf).unwrap();
+    assert_eq!(size, buf.len());
+    let after: Sodg<16> = Sodg::load_from(buf.as_slice()).unwrap();
+    assert_eq!(g, after);
+    let msg = format!(
+        "{:#}",
+        Sodg::<16>::load_from(&buf[..buf.len() - 5]).unwrap_err()
+    );
+    assert!(msg.contains("truncated"), "{msg}");
+}
+
+#[test]
+fn loads_back_to_back() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.put(0, &Hex::from(42));
+    let mut h: Sodg<16> = Sodg::empty(256);
+    h.add(0);
+    h.add(1);
+    h.bind(0, 1, Label::from_str("foo").unwrap());
+    let mut buf = vec![];
+    g.save_to(&mut buf).unwrap();
+    h.save_to(&mut buf).unwrap();
+    let mut stream = buf.as_slice();
+    assert_eq!(g, Sodg::<16>::load_from(&mut stream).unwrap());
+    assert_eq!(h, Sodg::<16>::load_from(&mut stream).unwrap());
+    assert!(stream.is_empty());
+}
+
+#[test]
+fn skips_dead_vertices() {
+    let mut g: Sodg<16> = Sodg::empty(100_000);
+    g.add(0);
+    g.add(99_999);
+    g.bind(0, 99_999, Label::from_str("far").unwrap());
+    g.put(99_999, &Hex::from(42));
+    let mut buf = vec![];
+    let size = g.save_to(&mut buf).unwrap();
+    assert!(size < 1000, "{size}");
+    let mut after: Sodg<16> = Sodg::load_from(buf.as_slice()).unwrap();
+    assert_eq!(g, after);
+    assert_eq!(100_000, after.vertices.capacity());
+    assert_eq!(Hex::from(42), after.data(99_999).unwrap());
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn compresses_body() {
+    let mut g: Sodg<16> = Sodg::empty(1000);
+    for v in 0..1000 {
+        g.add(v);
+        g.put(v, &Hex::from_str_bytes("the same text, again and again"));
+    }
+    let mut buf = vec![];
+    let (size, raw) = g.write_to(&mut buf, true).unwrap();
+    assert!(ratio(raw, size) > 2.0, "{raw} vs. {size}");
+    let after: Sodg<16> = Sodg::load_from(buf.as_slice()).unwrap();
+    assert_eq!(g, after);
+    let msg = format!(
+        "{:#}",
+        Sodg::<16>::load_from(&buf[..buf.len() - 20]).unwrap_err()
+    );
+    assert!(
+        msg.contains("truncated") || msg.contains("corrupted"),
+        "{msg}"
+    );
+}
+
+#[cfg(not(feature = "compression"))]
+#[test]
+fn refuses_compressed_body() {
+    let header = Header {
+        flags: FLAG_DEFLATE,
+        arity: 16,
+        capacity: 100,
+        labels: vec![],
+    };
+    let mut bytes = headed(&header);
+    bytes.extend([0; 16]);
+    let msg = format!("{:#}", Sodg::<16>::load_from(bytes.as_slice()).unwrap_err());
+    assert!(msg.contains("'compression' feature"), "{msg}");
+}
+
+#[test]
+fn saves_atomically() {
+    let mut g: Sodg<16> = Sodg::empty(100);
+    g.add(0);
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodg");
+    g.save(file.as_path()).unwrap();
+    let before = fs::read(file.as_path()).unwrap();
+    let msg = format!(
+        "{:#}",
+        write_atomically(file.as_path(), |w| {
+            w.write_all(b"garbage")?;
+            Err::<(), _>(anyhow!("A crash in the middle"))
+        })
+        .unwrap_err()
+    );
+    assert!(msg.contains("crash"), "{msg}");
+    assert_eq!(before, fs::read(file.as_path()).unwrap());
+    assert_eq!(1, fs::read_dir(tmp.path()).unwrap().count());
+}
+
+#[test]
+fn saves_concurrently() {
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodg");
+    std::thread::scope(|scope| {
+        for total in 1..8 {
+            let file = file.clone();
+            scope.spawn(move || {
+                let mut g: Sodg<16> = Sodg::empty(100);
+                for v in 0..total {
+                    g.add(v);
+                }
+                for _ in 0..10 {
+                    g.save(file.as_path()).unwrap();
+                }
+            });
+        }
+    });
+    assert!(!Sodg::<16>::load(file.as_path()).unwrap().is_empty());
+    assert_eq!(1, fs::read_dir(tmp.path()).unwrap().count());
+}
+
+#[test]
+fn detects_truncated_file() {
+    let mut g: Sodg<16> = Sodg::empty(100);
+    for v in 0..10 {
+        g.add(v);
+        g.put(v, &Hex::from_str_bytes("some data"));
+    }
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodg");
+    g.save(file.as_path()).unwrap();
+    let bytes = fs::read(file.as_path()).unwrap();
+    for cut in [1, 12, bytes.len() / 2] {
+        fs::write(file.as_path(), &bytes[..bytes.len() - cut]).unwrap();
+        let msg = format!("{:#}", Sodg::<16>::load(file.as_path()).unwrap_err());
+        assert!(
+            msg.contains("truncated") || msg.contains("corrupted"),
+            "{cut}: {msg}"
+        );
+    }
+}
+
+#[test]
+fn detects_broken_header() {
+    let mut g: Sodg<16> = Sodg::empty(100);
+    g.add(0);
+    let mut buf = vec![];
+    g.save_to(&mut buf).unwrap();
+    buf[12] ^= 0x10;
+    let msg = format!("{:#}", Sodg::<16>::load_from(buf.as_slice()).unwrap_err());
+    assert!(msg.contains("checksum of the header"), "{msg}");
+}
+
+#[test]
+fn refuses_huge_capacity() {
+    let bytes = headed(&Header {
+        flags: 0,
+        arity: 16,
+        capacity: 1 << 44,
+        labels: vec![],
+    });
+    let msg = format!("{:#}", Sodg::<16>::load_from(bytes.as_slice()).unwrap_err());
+    assert!(msg.contains("is too big"), "{msg}");
+}
+
+#[test]
+fn refuses_too_many_edges() {
+    let mut g: Sodg<16> = Sodg::empty(100);
+    for v in 0..4 {
+        g.add(v);
+    }
+    for v in 1..4 {
+        g.bind(0, v, Label::Alpha(v));
+    }
+    let labels = g.labels();
+    let mut body = vec![];
+    g.write_body(&mut body, &labels).unwrap();
+    let mut bytes = headed(&Header {
+        flags: 0,
+        arity: 2,
+        capacity: 100,
+        labels,
+    });
+    bytes.extend(&body);
+    bytes.extend(crc32fast::hash(&body).to_le_bytes());
+    bytes.extend((body.len() as u64).to_le_bytes());
+    let msg = format!("{:#}", Sodg::<2>::load_from(bytes.as_slice()).unwrap_err());
+    assert!(msg.contains("3 edges, while 2 is the maximum"), "{msg}");
+}
+
+#[test]
+fn saves_uncompressed_by_default() {
+    let mut g: Sodg<16> = Sodg::empty(100);
+    g.add(0);
+    g.put(0, &Hex::from_str_bytes("hello"));
+    let mut buf = vec![];
+    g.save_to(&mut buf).unwrap();
+    assert_eq!([0, 0], buf[6..8]);
+    assert_eq!(g, Sodg::<16>::load_from(buf.as_slice()).unwrap());
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn saves_compressed_on_demand() {
+    let mut g: Sodg<16> = Sodg::empty(100);
+    g.add(0);
+    g.put(0, &Hex::from_str_bytes("hello"));
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodg");
+    g.save_compressed(file.as_path()).unwrap();
+    let bytes = fs::read(file.as_path()).unwrap();
+    assert_eq!(FLAG_DEFLATE.to_le_bytes(), bytes[6..8]);
+    assert_eq!(g, Sodg::<16>::load(file.as_path()).unwrap());
 }
@@ -1,10 +1,11 @@
 // SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
 // SPDX-License-Identifier: MIT
 
-use crate::{Label, Sodg};
+use crate::{Label, Persistence, Sodg};
 use anyhow::Result;
 use log::trace;
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::iter;
 
 impl<const N: usize> Sodg<N> {
     /// Take a slice of the graph, keeping only the vertex specified
@@ -29,6 +30,8 @@ impl<const N: usize> Sodg<N> {
     /// but only if the provided predicate agrees with the selection of
     /// the kids.
     ///
+    /// The data of vertices is copied too, see [`Sodg::slice_with`].
+    ///
     /// # Errors
     ///
     /// There could be errors too.
@@ -37,47 +40,229 @@ impl<const N: usize> Sodg<N> {
     ///
     /// If impossible to slice, an error will be returned.
     pub fn slice_some(&self, v: usize, p: impl Fn(usize, usize, Label) -> bool) -> Result<Self> {
-        let mut todo = HashSet::new();
-        let mut done = HashSet::new();
-        todo.insert(v);
-        loop {
-            if todo.is_empty() {
-                break;
-            }
-            let before: Vec<usize> = todo.drain().collect();
-            for v in before {
-                done.insert(v);
-                for e in &self.vertices.get(v).unwrap().edges {
-                    if done.contains(e.1) {
-                        continue;
-                    }
-                    if !p(v, *e.1, *e.0) {
-                        continue;
-                    }
-                    done.insert(*e.1);
-                    todo.insert(*e.1);
-                }
-            }
-        }
+        self.slice_with(v, p, true)
+    }
+
+    /// Take a slice of the graph, just like [`Sodg::slice_some`] does,
+    /// copying the data of vertices only if `data` is `true`.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Hex, Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("x").unwrap());
+    /// g.put(1, &Hex::from(42));
+    /// let mut slice = g.slice_with(0, |_, _, _| true, true).unwrap();
+    /// assert_eq!(Hex::from(42), slice.data(1).unwrap());
+    /// let mut slice = g.slice_with(0, |_, _, _| true, false).unwrap();
+    /// assert!(slice.data(1).is_none());
+    /// ```
+    ///
+    /// The data is copied without submitting the vertices of this graph
+    /// to garbage collection. The data already taken by [`Sodg::data`] stays
+    /// taken in the slice, while the data not yet taken stays stored, in
+    /// the branches of the slice, which are made by [`Sodg::bind`].
+    ///
+    /// # Errors
+    ///
+    /// If impossible to slice, an error will be returned.
+    ///
+    /// # Panics
+    ///
+    /// If the vertex `v` is absent, it will panic.
+    pub fn slice_with(
+        &self,
+        v: usize,
+        p: impl Fn(usize, usize, Label) -> bool,
+        data: bool,
+    ) -> Result<Self> {
+        let ids: HashMap<usize, usize> = iter::once(v)
+            .chain(self.bfs(v, p).map(|(_, _, _, to)| to))
+            .map(|v| (v, v))
+            .collect();
         let mut ng = Self::empty(self.vertices.capacity());
-        for (v1, vtx) in self.vertices.iter().filter(|(v, _)| done.contains(v)) {
-            if done.contains(&v1) {
-                ng.add(v1);
-            }
-            for (k, v2) in &vtx.edges {
-                if done.contains(v2) {
-                    ng.add(*v2);
-                    ng.bind(v1, *v2, *k);
-                }
-            }
-        }
+        self.copy_into(&mut ng, &ids, data);
+        trace!(
+            "#slice_with: taken {} vertices out of {} at ν{v}",
+            ng.len(),
+            self.len()
+        );
+        Ok(ng)
+    }
+
+    /// Take a slice of the graph, keeping only the vertex `v` and its kids,
+    /// which are not deeper than `max_depth` edges below it, reachable
+    /// through the edges approved by the predicate `p`, just like
+    /// in [`Sodg::slice_some`].
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("a").unwrap());
+    /// g.add(2);
+    /// g.bind(1, 2, Label::from_str("b").unwrap());
+    /// g.bind(1, 0, Label::Greek('ρ'));
+    /// let slice = g.slice_depth(1, 2, |_, _, a| a != Label::Greek('ρ')).unwrap();
+    /// assert_eq!(2, slice.len());
+    /// ```
+    ///
+    /// The data of vertices is copied too, just like in [`Sodg::slice_some`].
+    ///
+    /// # Errors
+    ///
+    /// If impossible to slice, an error will be returned.
+    ///
+    /// # Panics
+    ///
+    /// If the vertex `v` is absent, it will panic.
+    pub fn slice_depth(
+        &self,
+        v: usize,
+        max_depth: usize,
+        p: impl Fn(usize, usize, Label) -> bool,
+    ) -> Result<Self> {
+        let ids: HashMap<usize, usize> = iter::once(v)
+            .chain(
+                self.bfs(v, p)
+                    .take_while(|(depth, _, _, _)| *depth <= max_depth)
+                    .map(|(_, _, _, to)| to),
+            )
+            .map(|v| (v, v))
+            .collect();
+        let mut ng = Self::empty(self.vertices.capacity());
+        self.copy_into(&mut ng, &ids, true);
         trace!(
-            "#slice_some: taken {} vertices out of {} at ν{v}",
+            "#slice_depth: taken {} vertices out of {} at ν{v}, up to {max_depth} levels below",
             ng.len(),
             self.len()
         );
         Ok(ng)
     }
+
+    /// Take a slice of the graph, keeping only the vertex `v` and its kids,
+    /// reachable through the edges with the `labels`, or, if `exclude`
+    /// is `true`, through the edges with any labels but these ones.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("a").unwrap());
+    /// g.bind(1, 0, Label::Greek('ρ'));
+    /// assert_eq!(1, g.slice_labels(1, &[Label::Greek('ρ')], true).unwrap().len());
+    /// ```
+    ///
+    /// Just like in [`Sodg::slice_some`], the edges between the vertices
+    /// taken are kept, no matter what are their labels, and the data
+    /// of vertices is copied too.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to slice, an error will be returned.
+    ///
+    /// # Panics
+    ///
+    /// If the vertex `v` is absent, it will panic.
+    pub fn slice_labels(&self, v: usize, labels: &[Label], exclude: bool) -> Result<Self> {
+        self.slice_some(v, |_, _, a| labels.contains(&a) != exclude)
+    }
+
+    /// Take a slice of the graph, just like [`Sodg::slice_some`] does,
+    /// but renumber the vertices, so that they get IDs one after another,
+    /// starting from `root`, which becomes the ID of the vertex `v`.
+    ///
+    /// For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(42);
+    /// g.bind(0, 42, Label::from_str("x").unwrap());
+    /// let (slice, ids) = g.slice_compact(42, 0, 0, |_, _, _| true).unwrap();
+    /// assert_eq!(1, slice.len());
+    /// assert_eq!(Some(&0), ids.get(&42));
+    /// ```
+    ///
+    /// The capacity of the slice is just enough to fit all its vertices
+    /// and `spare` more vertices, which may be added to it later, for example,
+    /// with IDs given by [`Sodg::next_id`]. If `spare` is zero, there is
+    /// no room for new vertices after the vertices of the slice.
+    /// The vertices are numbered in the order they are found by [`Sodg::bfs`].
+    /// The map of old IDs to new ones is returned together with the slice.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to slice, an error will be returned.
+    ///
+    /// # Panics
+    ///
+    /// If the vertex `v` is absent, it will panic.
+    pub fn slice_compact(
+        &self,
+        v: usize,
+        root: usize,
+        spare: usize,
+        p: impl Fn(usize, usize, Label) -> bool,
+    ) -> Result<(Self, HashMap<usize, usize>)> {
+        let ids: HashMap<usize, usize> = iter::once(v)
+            .chain(self.bfs(v, p).map(|(_, _, _, to)| to))
+            .enumerate()
+            .map(|(i, v)| (v, root + i))
+            .collect();
+        let mut ng = Self::empty(root + ids.len() + spare);
+        self.copy_into(&mut ng, &ids, true);
+        trace!(
+            "#slice_compact: taken {} vertices out of {} at ν{v}, renumbered from ν{root}",
+            ng.len(),
+            self.len()
+        );
+        Ok((ng, ids))
+    }
+
+    /// Copy the vertices, which are the keys of `ids`, into the graph `ng`,
+    /// giving them the IDs, which are the values of `ids`. Only the edges
+    /// between these vertices are copied. The data is copied if `data` is `true`.
+    fn copy_into(&self, ng: &mut Self, ids: &HashMap<usize, usize>, data: bool) {
+        for v1 in ids.values() {
+            ng.add(*v1);
+        }
+        for (v1, vtx) in self.vertices.iter().filter(|(v, _)| ids.contains_key(v)) {
+            for (k, v2) in &vtx.edges {
+                if let Some(to) = ids.get(v2) {
+                    ng.bind(ids[&v1], *to, *k);
+                }
+            }
+        }
+        if data {
+            for (v1, to) in ids {
+                let vtx = self.vertices.get(*v1).unwrap();
+                match vtx.persistence {
+                    Persistence::Empty => {}
+                    Persistence::Stored => ng.put(*to, &vtx.data),
+                    Persistence::Taken => {
+                        let nvtx = ng.vertices.get_mut(*to).unwrap();
+                        nvtx.data = vtx.data.clone();
+                        nvtx.persistence = Persistence::Taken;
+                    }
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -121,3 +306,106 @@ fn skips_some_vertices() {
     assert_eq!(2, slice.len());
     assert_eq!(1, slice.kids(0).count());
 }
+
+#[cfg(test)]
+use crate::Hex;
+
+#[test]
+fn keeps_data_in_slice() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.add(2);
+    g.bind(0, 2, Label::from_str("b").unwrap());
+    g.put(1, &Hex::from(1));
+    g.put(2, &Hex::from(2));
+    g.put(2, &Hex::from(2));
+    g.data(2);
+    let mut slice = g.slice(0).unwrap();
+    assert!(slice.vertices.get(1).unwrap().persistence == Persistence::Stored);
+    assert!(slice.vertices.get(2).unwrap().persistence == Persistence::Taken);
+    assert_eq!(Hex::from(2), slice.data(2).unwrap());
+    assert_eq!(Hex::from(1), slice.data(1).unwrap());
+    assert_eq!(3, g.len());
+}
+
+#[test]
+fn keeps_branches_consistent() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.add(2);
+    g.bind(1, 2, Label::from_str("x").unwrap());
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.put(2, &Hex::from(7));
+    let mut slice = g.slice(0).unwrap();
+    let branch = slice.vertices.get(2).unwrap().branch;
+    assert_eq!(branch, slice.vertices.get(1).unwrap().branch);
+    assert_eq!(1, *slice.stores.get(branch).unwrap());
+    slice.data(2);
+    assert!(slice.vertices.get(2).unwrap().branch == 0);
+}
+
+#[test]
+fn makes_compact_slice() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(10);
+    g.add(200);
+    g.bind(10, 200, Label::from_str("a").unwrap());
+    g.add(150);
+    g.bind(200, 150, Label::from_str("b").unwrap());
+    g.bind(150, 10, Label::Greek('ρ'));
+    g.put(150, &Hex::from(42));
+    let (mut slice, ids) = g.slice_compact(200, 1, 0, |_, _, _| true).unwrap();
+    assert_eq!(4, slice.vertices.capacity());
+    assert_eq!(3, slice.len());
+    assert_eq!(Some(&1), ids.get(&200));
+    assert_eq!(Some(2), slice.kid(1, Label::from_str("b").unwrap()));
+    assert_eq!(Some(3), slice.kid(2, Label::Greek('ρ')));
+    assert_eq!(Hex::from(42), slice.data(2).unwrap());
+}
+
+#[test]
+fn leaves_room_in_compact_slice() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(10);
+    g.add(200);
+    g.bind(10, 200, Label::from_str("a").unwrap());
+    let (mut slice, _) = g.slice_compact(10, 0, 2, |_, _, _| true).unwrap();
+    assert_eq!(4, slice.vertices.capacity());
+    for _ in 0..2 {
+        let v = slice.next_id();
+        slice.add(v);
+    }
+    assert_eq!(vec![0, 1, 2, 3], slice.keys());
+}
+
+#[test]
+fn slices_by_depth_and_labels() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(5);
+    g.bind(0, 5, Label::from_str("x").unwrap());
+    g.bind(5, 0, Label::Greek('ρ'));
+    g.add(6);
+    g.bind(5, 6, Label::from_str("a").unwrap());
+    g.add(7);
+    g.bind(6, 7, Label::from_str("b").unwrap());
+    g.add(8);
+    g.bind(7, 8, Label::from_str("c").unwrap());
+    assert_eq!(4, g.slice_depth(5, 2, |_, _, _| true).unwrap().len());
+    assert_eq!(1, g.slice_depth(5, 0, |_, _, _| true).unwrap().len());
+    let slice = g
+        .slice_depth(5, 2, |_, _, a| a != Label::Greek('ρ'))
+        .unwrap();
+    assert_eq!(3, slice.len());
+    assert_eq!(vec![5, 6, 7], slice.keys());
+    let slice = g.slice_labels(5, &[Label::Greek('ρ')], true).unwrap();
+    assert_eq!(4, slice.len());
+    assert_eq!(0, slice.vertices.get(0).unwrap().branch);
+    let slice = g
+        .slice_labels(5, &[Label::from_str("a").unwrap()], false)
+        .unwrap();
+    assert_eq!(2, slice.len());
+}
@@ -0,0 +1,342 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
+// SPDX-License-Identifier: MIT
+
+use crate::{Edge, Label, Sodg};
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+
+impl<const N: usize> Sodg<N> {
+    /// Check whether the part of the graph reachable from the `root`
+    /// is a tree.
+    ///
+    /// Only the edges approved by the predicate `p` are taken into account,
+    /// which makes it possible to ignore back references, for example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("foo").unwrap());
+    /// g.bind(1, 0, Label::Greek('ρ'));
+    /// assert!(!g.is_tree(0, |_, _, _| true));
+    /// assert!(g.is_tree(0, |_, _, a| a != Label::Greek('ρ')));
+    /// ```
+    #[must_use]
+    pub fn is_tree(&self, root: usize, p: impl Fn(usize, usize, Label) -> bool) -> bool {
+        self.check_tree(root, p).is_ok()
+    }
+
+    /// Make sure the part of the graph reachable from the `root` is a tree,
+    /// taking into account only the edges approved by the predicate `p`.
+    ///
+    /// # Errors
+    ///
+    /// If it's not a tree, an error will be returned, explaining where
+    /// exactly there is a cycle or a vertex with more than one parent.
+    pub fn check_tree(&self, root: usize, p: impl Fn(usize, usize, Label) -> bool) -> Result<()> {
+        if let Some(cycle) = self.find_cycle(root, &p) {
+            return Err(anyhow!(
+                "There is a cycle at ν{}: {}",
+                cycle[0].0,
+                Self::print_edges(&cycle)
+            ));
+        }
+        let mut parents: HashMap<usize, Vec<(usize, Label)>> = HashMap::new();
+        for v in self.reachable(root, &p) {
+            for (a, to) in self.kids(v) {
+                if p(v, *to, *a) {
+                    parents.entry(*to).or_default().push((v, *a));
+                }
+            }
+        }
+        if let Some((v, many)) = parents
+            .into_iter()
+            .filter(|(_, all)| all.len() > 1)
+            .min_by_key(|(v, _)| *v)
+        {
+            return Err(anyhow!(
+                "The vertex ν{v} has {} parents: {}",
+                many.len(),
+                many.iter()
+                    .sorted()
+                    .map(|(from, a)| format!("ν{from}.{a}"))
+                    .join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Find a cycle in the part of the graph reachable from the `root`,
+    /// taking into account only the edges approved by the predicate `p`.
+    ///
+    /// The cycle is returned as a list of edges, where the last one
+    /// arrives to the vertex the first one departs from, for example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("a").unwrap());
+    /// g.add(2);
+    /// g.bind(1, 2, Label::from_str("b").unwrap());
+    /// g.bind(2, 1, Label::from_str("c").unwrap());
+    /// let cycle = g.find_cycle(0, |_, _, _| true).unwrap();
+    /// assert_eq!(vec![1, 2], cycle.iter().map(|e| e.0).collect::<Vec<usize>>());
+    /// ```
+    ///
+    /// If there are no cycles, `None` is returned.
+    #[must_use]
+    pub fn find_cycle(
+        &self,
+        root: usize,
+        p: impl Fn(usize, usize, Label) -> bool,
+    ) -> Option<Vec<Edge>> {
+        let mut done = HashSet::new();
+        let mut path: Vec<Edge> = vec![];
+        let mut active = HashSet::from([root]);
+        let mut stack = vec![(root, self.sorted_kids(root))];
+        while let Some((v, kids)) = stack.last_mut() {
+            let v = *v;
+            let Some((a, to)) = kids.pop() else {
+                stack.pop();
+                path.pop();
+                active.remove(&v);
+                done.insert(v);
+                continue;
+            };
+            if done.contains(&to) || !p(v, to, a) {
+                continue;
+            }
+            if active.contains(&to) {
+                let start = path.iter().position(|e| e.0 == to).unwrap_or(path.len());
+                let mut cycle = path[start..].to_vec();
+                cycle.push((v, a, to));
+                return Some(cycle);
+            }
+            active.insert(to);
+            path.push((v, a, to));
+            stack.push((to, self.sorted_kids(to)));
+        }
+        None
+    }
+
+    /// Find all strongly connected components of the graph, taking
+    /// into account only the edges approved by the predicate `p`.
+    ///
+    /// Every alive vertex belongs to exactly one component. Vertices
+    /// inside each component are sorted, while components are ordered
+    /// by their smallest vertices, for example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("a").unwrap());
+    /// g.add(2);
+    /// g.bind(1, 2, Label::from_str("b").unwrap());
+    /// g.bind(2, 1, Label::from_str("c").unwrap());
+    /// assert_eq!(vec![vec![0], vec![1, 2]], g.strongly_connected_components(|_, _, _| true));
+    /// ```
+    #[must_use]
+    pub fn strongly_connected_components(
+        &self,
+        p: impl Fn(usize, usize, Label) -> bool,
+    ) -> Vec<Vec<usize>> {
+        let mut index: HashMap<usize, usize> = HashMap::new();
+        let mut low: HashMap<usize, usize> = HashMap::new();
+        let mut pending: Vec<usize> = vec![];
+        let mut open: HashSet<usize> = HashSet::new();
+        let mut found = vec![];
+        for root in self.keys() {
+            if index.contains_key(&root) {
+                continue;
+            }
+            let mut stack = vec![(root, self.sorted_kids(root))];
+            index.insert(root, index.len());
+            low.insert(root, index[&root]);
+            pending.push(root);
+            open.insert(root);
+            while let Some((v, kids)) = stack.last_mut() {
+                let v = *v;
+                if let Some((a, to)) = kids.pop() {
+                    if !p(v, to, a) {
+                        continue;
+                    }
+                    if !index.contains_key(&to) {
+                        index.insert(to, index.len());
+                        low.insert(to, index[&to]);
+                        pending.push(to);
+                        open.insert(to);
+                        stack.push((to, self.sorted_kids(to)));
+                    } else if open.contains(&to) {
+                        low.insert(v, low[&v].min(index[&to]));
+                    }
+                    continue;
+                }
+                stack.pop();
+                if let Some((parent, _)) = stack.last() {
+                    low.insert(*parent, low[parent].min(low[&v]));
+                }
+                if low[&v] == index[&v] {
+                    let mut scc = vec![];
+                    while let Some(w) = pending.pop() {
+                        open.remove(&w);
+                        scc.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    scc.sort_unstable();
+                    found.push(scc);
+                }
+            }
+        }
+        found.sort_unstable_by_key(|scc| scc[0]);
+        found
+    }
+
+    /// Order all vertices reachable from the `root` so that every
+    /// vertex goes before all vertices it has edges to, taking
+    /// into account only the edges approved by the predicate `p`.
+    ///
+    /// The `root` is always the first one in the list, for example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.add(2);
+    /// g.bind(0, 2, Label::from_str("a").unwrap());
+    /// g.bind(0, 1, Label::from_str("b").unwrap());
+    /// g.bind(2, 1, Label::from_str("c").unwrap());
+    /// assert_eq!(vec![0, 2, 1], g.topological_order(0, |_, _, _| true).unwrap());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If there is a cycle, an error will be returned, explaining where it is.
+    pub fn topological_order(
+        &self,
+        root: usize,
+        p: impl Fn(usize, usize, Label) -> bool,
+    ) -> Result<Vec<usize>> {
+        if let Some(cycle) = self.find_cycle(root, &p) {
+            return Err(anyhow!(
+                "Can't order vertices from ν{root}, there is a cycle: {}",
+                Self::print_edges(&cycle)
+            ));
+        }
+        let mut done = HashSet::from([root]);
+        let mut order = vec![];
+        let mut stack = vec![(root, self.sorted_kids(root))];
+        while let Some((v, kids)) = stack.last_mut() {
+            let v = *v;
+            let Some((a, to)) = kids.pop() else {
+                stack.pop();
+                order.push(v);
+                continue;
+            };
+            if done.contains(&to) || !p(v, to, a) {
+                continue;
+            }
+            done.insert(to);
+            stack.push((to, self.sorted_kids(to)));
+        }
+        order.reverse();
+        Ok(order)
+    }
+
+    /// Get the `root` and all vertices reachable from it, through the
+    /// edges approved by the predicate `p`.
+    fn reachable(&self, root: usize, p: impl Fn(usize, usize, Label) -> bool) -> Vec<usize> {
+        let mut all = vec![root];
+        all.extend(self.bfs(root, p).map(|(_, _, _, v)| v));
+        all
+    }
+
+    /// Print a chain of edges, like `ν1.a → ν2.b → ν1`.
+    fn print_edges(edges: &[Edge]) -> String {
+        let mut parts: Vec<String> = edges.iter().map(|(v, a, _)| format!("ν{v}.{a}")).collect();
+        if let Some((_, _, to)) = edges.last() {
+            parts.push(format!("ν{to}"));
+        }
+        parts.join(" → ")
+    }
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn finds_no_cycle_in_dag() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.add(2);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.bind(0, 2, Label::from_str("b").unwrap());
+    g.bind(1, 2, Label::from_str("c").unwrap());
+    assert!(g.find_cycle(0, |_, _, _| true).is_none());
+    let msg = g.check_tree(0, |_, _, _| true).unwrap_err().to_string();
+    assert!(msg.contains("ν2 has 2 parents: ν0.b, ν1.c"), "{msg}");
+}
+
+#[test]
+fn finds_self_loop() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.bind(0, 0, Label::from_str("me").unwrap());
+    let cycle = g.find_cycle(0, |_, _, _| true).unwrap();
+    assert_eq!(vec![(0, Label::from_str("me").unwrap(), 0)], cycle);
+    let msg = g.check_tree(0, |_, _, _| true).unwrap_err().to_string();
+    assert!(msg.contains("ν0.me → ν0"), "{msg}");
+}
+
+#[test]
+fn accepts_tree_with_back_references() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("x").unwrap());
+    g.bind(1, 0, Label::Greek('ρ'));
+    g.add(2);
+    g.bind(1, 2, Label::from_str("y").unwrap());
+    g.bind(2, 1, Label::Greek('ρ'));
+    let p = |_, _, a| a != Label::Greek('ρ');
+    assert!(g.is_tree(0, p));
+    assert_eq!(vec![0, 1, 2], g.topological_order(0, p).unwrap());
+    assert!(g.topological_order(0, |_, _, _| true).is_err());
+    assert_eq!(
+        vec![vec![0, 1, 2]],
+        g.strongly_connected_components(|_, _, _| true)
+    );
+    assert_eq!(3, g.strongly_connected_components(p).len());
+}
+
+#[test]
+fn finds_components_in_two_loops() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    for v in 0..6 {
+        g.add(v);
+    }
+    g.bind(0, 1, Label::Alpha(0));
+    g.bind(1, 2, Label::Alpha(0));
+    g.bind(2, 0, Label::Alpha(0));
+    g.bind(2, 3, Label::Alpha(1));
+    g.bind(3, 4, Label::Alpha(0));
+    g.bind(4, 3, Label::Alpha(0));
+    g.bind(4, 5, Label::Alpha(1));
+    assert_eq!(
+        vec![vec![0, 1, 2], vec![3, 4], vec![5]],
+        g.strongly_connected_components(|_, _, _| true)
+    );
+}
@@ -0,0 +1,255 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
+// SPDX-License-Identifier: MIT
+
+use crate::{Bfs, Dfs, Label, Sodg};
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+impl<const N: usize> Sodg<N> {
+    /// Walk through all vertices reachable from the vertex `v`,
+    /// in breadth-first order.
+    ///
+    /// The predicate `p` is asked about every edge before walking
+    /// through it, getting the vertex the edge departs from, the vertex
+    /// it arrives to, and its label. The vertex `v` itself is not
+    /// returned by the iterator. Kids of every vertex are visited
+    /// in the order of their labels. For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("a").unwrap());
+    /// g.add(2);
+    /// g.bind(1, 2, Label::from_str("b").unwrap());
+    /// g.add(3);
+    /// g.bind(0, 3, Label::from_str("c").unwrap());
+    /// let found: Vec<usize> = g.bfs(0, |_, _, _| true).map(|(_, _, _, v)| v).collect();
+    /// assert_eq!(vec![1, 3, 2], found);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the vertex `v` is absent, it will panic.
+    pub fn bfs<P: Fn(usize, usize, Label) -> bool>(&self, v: usize, p: P) -> Bfs<'_, N, P> {
+        let mut bfs = Bfs {
+            g: self,
+            p,
+            seen: HashSet::from([v]),
+            todo: VecDeque::new(),
+        };
+        bfs.expand(0, v);
+        bfs
+    }
+
+    /// Walk through all vertices reachable from the vertex `v`,
+    /// in depth-first order.
+    ///
+    /// The predicate `p` is asked about every edge before walking
+    /// through it, exactly as in [`Sodg::bfs`]. The vertex `v` itself is not
+    /// returned by the iterator. Kids of every vertex are visited
+    /// in the order of their labels. For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("a").unwrap());
+    /// g.add(2);
+    /// g.bind(1, 2, Label::from_str("b").unwrap());
+    /// g.add(3);
+    /// g.bind(0, 3, Label::from_str("c").unwrap());
+    /// let found: Vec<usize> = g.dfs(0, |_, _, _| true).map(|(_, _, _, v)| v).collect();
+    /// assert_eq!(vec![1, 2, 3], found);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the vertex `v` is absent, it will panic.
+    pub fn dfs<P: Fn(usize, usize, Label) -> bool>(&self, v: usize, p: P) -> Dfs<'_, N, P> {
+        Dfs {
+            g: self,
+            p,
+            seen: HashSet::from([v]),
+            stack: vec![(0, v, self.sorted_kids(v))],
+        }
+    }
+
+    /// Find the shortest path from the vertex `from` to the vertex `to`.
+    ///
+    /// The path is a list of steps, where every step is the vertex
+    /// an edge departs from together with the label of the edge. If there
+    /// are many shortest paths, the one with the smallest labels is taken.
+    /// For example:
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use sodg::{Label, Sodg};
+    /// let mut g : Sodg<16> = Sodg::empty(256);
+    /// g.add(0);
+    /// g.add(1);
+    /// g.bind(0, 1, Label::from_str("foo").unwrap());
+    /// g.add(2);
+    /// g.bind(1, 2, Label::from_str("bar").unwrap());
+    /// let path = g.path(0, 2).unwrap();
+    /// assert_eq!(vec![(0, Label::from_str("foo").unwrap()), (1, Label::from_str("bar").unwrap())], path);
+    /// ```
+    ///
+    /// If `to` is not reachable from `from`, `None` is returned. If they
+    /// are the same vertex, the path is empty.
+    ///
+    /// # Panics
+    ///
+    /// If the vertex `from` is absent, it will panic.
+    #[must_use]
+    pub fn path(&self, from: usize, to: usize) -> Option<Vec<(usize, Label)>> {
+        if from == to {
+            return Some(vec![]);
+        }
+        let mut back: HashMap<usize, (usize, Label)> = HashMap::new();
+        for (_, parent, a, v) in self.bfs(from, |_, _, _| true) {
+            back.insert(v, (parent, a));
+            if v == to {
+                let mut path = vec![];
+                let mut here = to;
+                while here != from {
+                    let step = back[&here];
+                    path.push(step);
+                    here = step.0;
+                }
+                path.reverse();
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    /// Get all kids of a vertex, in reverse order of their labels.
+    pub(crate) fn sorted_kids(&self, v: usize) -> Vec<(Label, usize)> {
+        self.kids(v)
+            .map(|(a, to)| (*a, *to))
+            .sorted()
+            .rev()
+            .collect()
+    }
+}
+
+impl<const N: usize, P: Fn(usize, usize, Label) -> bool> Bfs<'_, N, P> {
+    /// Discover all kids of the vertex `v`, which is at the `depth`.
+    fn expand(&mut self, depth: usize, v: usize) {
+        for (a, to) in self.g.sorted_kids(v).into_iter().rev() {
+            if self.seen.contains(&to) || !(self.p)(v, to, a) {
+                continue;
+            }
+            self.seen.insert(to);
+            self.todo.push_back((depth + 1, v, a, to));
+        }
+    }
+}
+
+impl<const N: usize, P: Fn(usize, usize, Label) -> bool> Iterator for Bfs<'_, N, P> {
+    type Item = (usize, usize, Label, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.todo.pop_front()?;
+        self.expand(next.0, next.3);
+        Some(next)
+    }
+}
+
+impl<const N: usize, P: Fn(usize, usize, Label) -> bool> Iterator for Dfs<'_, N, P> {
+    type Item = (usize, usize, Label, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (depth, v, kids) = self.stack.last_mut()?;
+            let (depth, v) = (*depth, *v);
+            let Some((a, to)) = kids.pop() else {
+                self.stack.pop();
+                continue;
+            };
+            if self.seen.contains(&to) || !(self.p)(v, to, a) {
+                continue;
+            }
+            self.seen.insert(to);
+            self.stack.push((depth + 1, to, self.g.sorted_kids(to)));
+            return Some((depth + 1, v, a, to));
+        }
+    }
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn walks_breadth_first() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("a").unwrap());
+    g.add(2);
+    g.bind(1, 2, Label::from_str("b").unwrap());
+    g.bind(2, 0, Label::from_str("c").unwrap());
+    let all: Vec<(usize, usize, Label, usize)> = g.bfs(0, |_, _, _| true).collect();
+    assert_eq!(
+        vec![
+            (1, 0, Label::from_str("a").unwrap(), 1),
+            (2, 1, Label::from_str("b").unwrap(), 2)
+        ],
+        all
+    );
+}
+
+#[test]
+fn walks_depth_first() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::Alpha(0));
+    g.add(2);
+    g.bind(0, 2, Label::Alpha(1));
+    g.add(3);
+    g.bind(1, 3, Label::Alpha(0));
+    g.bind(3, 2, Label::Alpha(0));
+    let depths: Vec<(usize, usize)> = g
+        .dfs(0, |_, _, _| true)
+        .map(|(d, _, _, v)| (d, v))
+        .collect();
+    assert_eq!(vec![(1, 1), (2, 3), (3, 2)], depths);
+}
+
+#[test]
+fn skips_filtered_edges() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::Greek('ρ'));
+    g.add(2);
+    g.bind(0, 2, Label::Alpha(0));
+    let p = |_, _, a| a != Label::Greek('ρ');
+    assert_eq!(1, g.bfs(0, p).count());
+    assert_eq!(1, g.dfs(0, p).count());
+}
+
+#[test]
+fn finds_shortest_path() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    for v in 0..5 {
+        g.add(v);
+    }
+    g.bind(0, 1, Label::Alpha(0));
+    g.bind(1, 2, Label::Alpha(0));
+    g.bind(2, 3, Label::Alpha(0));
+    g.bind(0, 4, Label::Alpha(1));
+    g.bind(4, 3, Label::Alpha(0));
+    assert_eq!(
+        vec![(0, Label::Alpha(1)), (4, Label::Alpha(0))],
+        g.path(0, 3).unwrap()
+    );
+    assert!(g.path(3, 0).is_none());
+    assert!(g.path(3, 3).unwrap().is_empty());
+}
@@ -0,0 +1,180 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
+// SPDX-License-Identifier: MIT
+
+use crate::{Fallback, SodgView, Vertex, Vertices};
+use itertools::Either;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::iter;
+use std::sync::OnceLock;
+
+impl<const N: usize> Vertices<N> {
+    /// Make room for `cap` vertices, none of them alive.
+    pub(crate) fn with_capacity(cap: usize) -> Self {
+        Self {
+            map: emap::Map::with_capacity_some(cap, Vertex::empty()),
+            fallback: None,
+        }
+    }
+
+    /// Make room for all vertices of the view, which are read
+    /// from it only when they are asked for, and copied into the map
+    /// only when they are modified.
+    pub(crate) fn backed_by(view: SodgView<N>) -> Self {
+        let cap = view.capacity();
+        let mut all = Self::with_capacity(cap);
+        all.fallback = Some(Box::new(Fallback {
+            view,
+            copied: vec![0; cap.div_ceil(64)],
+            read: iter::repeat_with(OnceLock::new).take(cap).collect(),
+        }));
+        all
+    }
+
+    /// Get the total number of vertices there may be.
+    #[inline]
+    pub(crate) const fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// Get the vertex, reading it from the view, if it's not copied yet.
+    #[inline]
+    pub(crate) fn get(&self, v: usize) -> Option<&Vertex<N>> {
+        match &self.fallback {
+            Some(f) if !f.is_copied(v) => f.read(v).or_else(|| self.map.get(v)),
+            _ => self.map.get(v),
+        }
+    }
+
+    /// Get the vertex in order to modify it, copying it from the view,
+    /// if it's not copied yet.
+    #[inline]
+    pub(crate) fn get_mut(&mut self, v: usize) -> Option<&mut Vertex<N>> {
+        if let Some(f) = &mut self.fallback {
+            if !f.is_copied(v) {
+                if let Some(vtx) = f.read[v].take().or_else(|| f.view.vertex(v)) {
+                    self.map.insert(v, vtx);
+                }
+                f.copied[v / 64] |= 1 << (v % 64);
+            }
+        }
+        self.map.get_mut(v)
+    }
+
+    /// Get the vertex without keeping it, if it's read from the view.
+    ///
+    /// # Panics
+    ///
+    /// If the vertex is outside of the capacity, it will panic.
+    pub(crate) fn peek(&self, v: usize) -> Cow<'_, Vertex<N>> {
+        match &self.fallback {
+            Some(f) if !f.is_copied(v) => f.read[v].get().map_or_else(
+                || {
+                    f.view
+                        .vertex(v)
+                        .map_or_else(|| Cow::Borrowed(self.map.get(v).unwrap()), Cow::Owned)
+                },
+                Cow::Borrowed,
+            ),
+            _ => Cow::Borrowed(self.map.get(v).unwrap()),
+        }
+    }
+
+    /// Is the vertex alive? It is not read from the view, if it's not
+    /// copied yet.
+    ///
+    /// # Panics
+    ///
+    /// If the vertex is outside of the capacity, it will panic.
+    #[inline]
+    pub(crate) fn is_alive(&self, v: usize) -> bool {
+        match &self.fallback {
+            Some(f) if !f.is_copied(v) => f.view.contains(v),
+            _ => self.map.get(v).unwrap().branch != 0,
+        }
+    }
+
+    /// Iterate over IDs of all alive vertices, in ascending order, without
+    /// reading vertices from the view.
+    pub(crate) fn alive(&self) -> impl Iterator<Item = usize> + '_ {
+        self.fallback.as_ref().map_or_else(
+            || {
+                Either::Left(
+                    self.map
+                        .iter()
+                        .filter(|(_, vtx)| vtx.branch != 0)
+                        .map(|(v, _)| v),
+                )
+            },
+            |f| {
+                let mut listed = f.view.ids().peekable();
+                Either::Right((0..self.capacity()).filter(move |v| {
+                    while listed.next_if(|id| id < v).is_some() {}
+                    if f.is_copied(*v) {
+                        self.map.get(*v).unwrap().branch != 0
+                    } else {
+                        listed.peek() == Some(v)
+                    }
+                }))
+            },
+        )
+    }
+
+    /// Iterate over all vertices, alive or not, together with their IDs,
+    /// reading them from the view, if they are not copied yet.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (usize, &Vertex<N>)> + '_ {
+        (0..self.capacity()).filter_map(|v| self.get(v).map(|vtx| (v, vtx)))
+    }
+}
+
+impl<const N: usize> Fallback<N> {
+    /// Is the vertex copied into the map already? Vertices outside
+    /// of the capacity are never read from the view.
+    #[inline]
+    fn is_copied(&self, v: usize) -> bool {
+        v >= self.read.len() || self.copied[v / 64] & (1 << (v % 64)) != 0
+    }
+
+    /// Read the vertex from the view, if it's there, and keep it.
+    fn read(&self, v: usize) -> Option<&Vertex<N>> {
+        let cell = self.read.get(v)?;
+        if let Some(vtx) = cell.get() {
+            return Some(vtx);
+        }
+        self.view.vertex(v).map(|vtx| cell.get_or_init(|| vtx))
+    }
+}
+
+impl<const N: usize> Serialize for Vertices<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.fallback.is_none() {
+            return self.map.serialize(serializer);
+        }
+        let mut all = emap::Map::with_capacity_none(self.capacity());
+        for v in 0..self.capacity() {
+            all.insert(v, self.peek(v).into_owned());
+        }
+        all.serialize(serializer)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for Vertices<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self {
+            map: emap::Map::deserialize(deserializer)?,
+            fallback: None,
+        })
+    }
+}
+
+#[test]
+fn finds_alive_vertices() {
+    let mut all: Vertices<16> = Vertices::with_capacity(100);
+    all.get_mut(7).unwrap().branch = 1;
+    all.get_mut(42).unwrap().branch = 1;
+    assert_eq!(1, all.get(7).unwrap().branch);
+    assert!(all.is_alive(42));
+    assert!(!all.is_alive(43));
+    assert_eq!(vec![7, 42], all.alive().collect::<Vec<usize>>());
+    assert_eq!(100, all.iter().count());
+}
@@ -0,0 +1,550 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022-2025 Objectionary.com
+// SPDX-License-Identifier: MIT
+
+use crate::serialization::write_atomically;
+use crate::{Hex, Label, Persistence, Sodg, SodgView, Vertex, Vertices};
+use anyhow::{anyhow, Context, Result};
+use bincode::{deserialize, serialize};
+use itertools::Itertools;
+use log::trace;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// The first bytes of every file made by [`Sodg::save_view`].
+const MAGIC: &[u8; 4] = b"SODV";
+
+/// The version of the layout of files made by [`Sodg::save_view`].
+const VERSION: u16 = 1;
+
+/// The size of the header, in bytes.
+///
+/// The header contains the [`MAGIC`], the [`VERSION`], two reserved bytes,
+/// the `N` of [`Sodg`] as `u32`, four reserved bytes, and then eight `u64`
+/// numbers: the capacity, the state of [`Sodg::next_id`], the positions of
+/// the edges and the data, the position and the size of the labels,
+/// and the position and the size of the branches. All numbers
+/// are little-endian.
+const HEADER: usize = 80;
+
+/// The size of an entry of a vertex, in bytes.
+///
+/// Right after the header, there is an entry for every alive vertex,
+/// sorted by their IDs, so that the entry of a vertex is found by binary
+/// search. The entry contains the ID and the branch as `u64`, the persistence
+/// of the data as `u32`, the number of edges as `u32`, and then three `u64`
+/// numbers: the position of the first edge among all edges, the position
+/// of the data among all data, and the size of the data.
+const ENTRY: usize = 48;
+
+/// The size of an edge, in bytes: the position of its label among
+/// all labels as `u32`, four reserved bytes, and the target as `u64`.
+const EDGE: usize = 16;
+
+impl<const N: usize> Sodg<N> {
+    /// Save the entire [`Sodg`] into a file with a fixed layout,
+    /// which may later be memory-mapped by [`SodgView::open`].
+    ///
+    /// The function returns the size of the file just saved. Only alive
+    /// vertices are saved, each of them with an entry of a fixed size,
+    /// and the file is written as it goes, without building it in memory.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to save, an error will be returned.
+    ///
+    /// # Panics
+    ///
+    /// If the graph is broken inside, it may panic.
+    pub fn save_view(&self, path: &Path) -> Result<usize> {
+        let start = Instant::now();
+        let alive = self.keys();
+        let labels = self.labels();
+        let positions: HashMap<Label, u32> = labels
+            .iter()
+            .enumerate()
+            .map(|(i, a)| Ok((*a, u32::try_from(i)?)))
+            .collect::<Result<_>>()?;
+        let (mut edges, mut data) = (0, 0);
+        for v in &alive {
+            let vtx = self.vertices.peek(*v);
+            edges += vtx.edges.len() * EDGE;
+            if vtx.persistence != Persistence::Empty {
+                data += vtx.data.len();
+            }
+        }
+        let names = serialize(&labels)?;
+        let branches = serialize(&(&self.stores, &self.branches))?;
+        let mut pos = HEADER + alive.len() * ENTRY;
+        let mut header = Vec::with_capacity(HEADER);
+        header.extend(MAGIC);
+        header.extend(VERSION.to_le_bytes());
+        header.extend([0; 2]);
+        header.extend(u32::try_from(N)?.to_le_bytes());
+        header.extend([0; 4]);
+        header.extend((self.vertices.capacity() as u64).to_le_bytes());
+        header.extend((self.next_v as u64).to_le_bytes());
+        for size in [edges, data, names.len()] {
+            header.extend((pos as u64).to_le_bytes());
+            pos += size;
+        }
+        header.extend((names.len() as u64).to_le_bytes());
+        header.extend((pos as u64).to_le_bytes());
+        header.extend((branches.len() as u64).to_le_bytes());
+        let size = pos + branches.len();
+        write_atomically(path, |w| {
+            w.write_all(&header)?;
+            self.write_entries(w, &alive)?;
+            for v in &alive {
+                for (a, to) in self.vertices.peek(*v).edges.iter().sorted() {
+                    w.write_all(&positions[a].to_le_bytes())?;
+                    w.write_all(&0_u32.to_le_bytes())?;
+                    w.write_all(&(*to as u64).to_le_bytes())?;
+                }
+            }
+            for v in &alive {
+                let vtx = self.vertices.peek(*v);
+                if vtx.persistence != Persistence::Empty {
+                    w.write_all(vtx.data.bytes())?;
+                }
+            }
+            w.write_all(&names)?;
+            w.write_all(&branches)?;
+            Ok(())
+        })?;
+        trace!(
+            "Saved a view of {} vertices ({} bytes) to {} in {:?}",
+            alive.len(),
+            size,
+            path.display(),
+            start.elapsed()
+        );
+        Ok(size)
+    }
+
+    /// Write the entries of alive vertices, one by one, in the order given.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to write, an error will be returned.
+    fn write_entries<W: Write>(&self, w: &mut W, alive: &[usize]) -> Result<()> {
+        let (mut first, mut pos) = (0_u64, 0_u64);
+        for v in alive {
+            let vtx = self.vertices.peek(*v);
+            let size = if vtx.persistence == Persistence::Empty {
+                0
+            } else {
+                vtx.data.len() as u64
+            };
+            w.write_all(&(*v as u64).to_le_bytes())?;
+            w.write_all(&(vtx.branch as u64).to_le_bytes())?;
+            w.write_all(
+                &match vtx.persistence {
+                    Persistence::Empty => 0_u32,
+                    Persistence::Stored => 1,
+                    Persistence::Taken => 2,
+                }
+                .to_le_bytes(),
+            )?;
+            w.write_all(&u32::try_from(vtx.edges.len())?.to_le_bytes())?;
+            w.write_all(&first.to_le_bytes())?;
+            w.write_all(&pos.to_le_bytes())?;
+            w.write_all(&size.to_le_bytes())?;
+            first += vtx.edges.len() as u64;
+            pos += size;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> SodgView<N> {
+    /// Map the file, previously saved by [`Sodg::save_view`], into memory.
+    ///
+    /// Only the header and the labels are read, while vertices, edges,
+    /// and data stay in the file until they are asked for. The file must not
+    /// be modified while it is mapped.
+    ///
+    /// # Errors
+    ///
+    /// If impossible to map, an error will be returned. For example, if
+    /// the file was saved from a [`Sodg`] with a different `N`, or if it is
+    /// not a view at all.
+    pub fn open(path: &Path) -> Result<Self> {
+        let start = Instant::now();
+        let file =
+            File::open(path).with_context(|| format!("Can't read from {}", path.display()))?;
+        // SAFETY: the mapping is read-only and the file is not expected
+        // to be modified while it is mapped, as the documentation says.
+        let map = unsafe { memmap2::Mmap::map(&file) }
+            .with_context(|| format!("Can't map {} into memory", path.display()))?;
+        let view = Self::from_map(map)
+            .with_context(|| format!("Can't open a view of {}", path.display()))?;
+        trace!(
+            "Mapped a view of {} vertices ({} bytes) from {} in {:?}",
+            view.entries,
+            view.map.len(),
+            path.display(),
+            start.elapsed()
+        );
+        Ok(view)
+    }
+
+    /// Check the header of the mapping and make a view of it.
+    ///
+    /// # Errors
+    ///
+    /// If the header is broken or doesn't fit, an error will be returned.
+    fn from_map(map: memmap2::Mmap) -> Result<Self> {
+        if map.len() < HEADER || &map[..MAGIC.len()] != MAGIC {
+            return Err(anyhow!("There is no SODV header, it is not a view"));
+        }
+        let version = u16::from_le_bytes(map[4..6].try_into()?);
+        if version != VERSION {
+            return Err(anyhow!(
+                "The layout version {version} is not supported, only {VERSION} is known"
+            ));
+        }
+        let arity = u32::from_le_bytes(map[8..12].try_into()?);
+        if arity != u32::try_from(N)? {
+            return Err(anyhow!(
+                "The file contains Sodg<{arity}>, while Sodg<{N}> is expected"
+            ));
+        }
+        let number = |i: usize| -> Result<usize> {
+            Ok(usize::try_from(u64::from_le_bytes(
+                map[16 + i * 8..24 + i * 8].try_into()?,
+            ))?)
+        };
+        let capacity = number(0)?;
+        let (edges, data) = (number(2)?, number(3)?);
+        let (names, size) = (number(4)?, number(5)?);
+        let (branches, len) = (number(6)?, number(7)?);
+        let table = edges.checked_sub(HEADER).unwrap_or(usize::MAX);
+        let entries = table / ENTRY;
+        if branches.checked_add(len) != Some(map.len())
+            || table % ENTRY != 0
+            || entries > capacity
+            || data < edges
+            || (data - edges) % EDGE != 0
+            || !(data <= names && names.checked_add(size).is_some_and(|end| end <= branches))
+        {
+            return Err(anyhow!(
+                "The layout of the view of {} bytes is broken, the file is corrupted",
+                map.len()
+            ));
+        }
+        let labels = deserialize(&map[names..names + size])
+            .with_context(|| "Can't read the labels, the file is corrupted")?;
+        Ok(Self {
+            map: Arc::new(map),
+            labels,
+            capacity,
+            entries,
+            edges,
+            data,
+        })
+    }
+
+    /// Get the total number of vertices the graph may have.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Find all kids of a vertex, reading them right from the mapping.
+    ///
+    /// # Panics
+    ///
+    /// If vertex `v` is outside of the capacity of the graph, or
+    /// if the file is corrupted, it will panic.
+    pub fn kids(&self, v: usize) -> impl Iterator<Item = (Label, usize)> + '_ {
+        let (first, total) = self.entry(v).map_or((self.edges, 0), |entry| {
+            (
+                self.edges + self.u64_at(entry + 24) * EDGE,
+                self.u32_at(entry + 20) as usize,
+            )
+        });
+        (0..total).map(move |i| {
+            let edge = first + i * EDGE;
+            (
+                self.labels[self.u32_at(edge) as usize],
+                self.u64_at(edge + 8),
+            )
+        })
+    }
+
+    /// Find a kid of a vertex, by its edge name, reading it right
+    /// from the mapping.
+    ///
+    /// # Panics
+    ///
+    /// If vertex `v` is outside of the capacity of the graph, or
+    /// if the file is corrupted, it will panic.
+    #[must_use]
+    pub fn kid(&self, v: usize, a: Label) -> Option<usize> {
+        self.kids(v).find(|(e, _)| *e == a).map(|(_, to)| to)
+    }
+
+    /// Read the data of a vertex right from the mapping.
+    ///
+    /// Unlike [`Sodg::data`], the data is not marked as taken, since
+    /// the view is read-only: no garbage is collected in it.
+    ///
+    /// # Panics
+    ///
+    /// If vertex `v` is outside of the capacity of the graph, or
+    /// if the file is corrupted, it will panic.
+    #[must_use]
+    pub fn data(&self, v: usize) -> Option<Hex> {
+        let entry = self.entry(v)?;
+        if self.u32_at(entry + 16) == 0 {
+            return None;
+        }
+        let pos = self.data + self.u64_at(entry + 32);
+        Some(Hex::from_slice(
+            &self.map[pos..pos + self.u64_at(entry + 40)],
+        ))
+    }
+
+    /// Make a [`Sodg`], which can be modified, from the view.
+    ///
+    /// The promotion is copy-on-write: the vertices are not copied right
+    /// away, but one by one, when they are touched for the first time,
+    /// either read or modified. The mapping is never modified, thus
+    /// the view stays as it is, and it may be promoted again.
+    ///
+    /// # Errors
+    ///
+    /// If the file is corrupted, an error will be returned.
+    ///
+    /// # Panics
+    ///
+    /// If the layout of the file is broken, it may panic.
+    pub fn promote(&self) -> Result<Sodg<N>> {
+        let mut prev = None;
+        for i in 0..self.entries {
+            let entry = HEADER + i * ENTRY;
+            let v = self.u64_at(entry);
+            if v >= self.capacity || prev.is_some_and(|p| p >= v) || self.u64_at(entry + 8) == 0 {
+                return Err(anyhow!(
+                    "The entry no.{i} of ν{v} is out of order, the file is corrupted"
+                ));
+            }
+            let p = self.u32_at(entry + 16);
+            if p > 2 {
+                return Err(anyhow!(
+                    "The persistence {p} of ν{v} is unknown, the file is corrupted"
+                ));
+            }
+            prev = Some(v);
+        }
+        let mut g = Sodg::empty(0);
+        g.next_v = self.u64_at(24);
+        let (at, size) = (self.u64_at(64), self.u64_at(72));
+        (g.stores, g.branches) = deserialize(&self.map[at..at + size])
+            .with_context(|| "Can't read the branches, the file is corrupted")?;
+        g.vertices = Vertices::backed_by(self.clone());
+        Ok(g)
+    }
+
+    /// Make a copy of the vertex, reading it from the mapping,
+    /// if it's alive.
+    ///
+    /// # Panics
+    ///
+    /// If the file is corrupted, it will panic.
+    pub(crate) fn vertex(&self, v: usize) -> Option<Vertex<N>> {
+        let entry = self.entry(v)?;
+        let mut vtx = Vertex::empty();
+        vtx.branch = self.u64_at(entry + 8);
+        vtx.persistence = match self.u32_at(entry + 16) {
+            0 => Persistence::Empty,
+            1 => Persistence::Stored,
+            _ => Persistence::Taken,
+        };
+        vtx.data = self.data(v).unwrap_or_else(Hex::empty);
+        for (a, to) in self.kids(v) {
+            vtx.edges.insert(a, to);
+        }
+        Some(vtx)
+    }
+
+    /// Is the vertex alive in the view?
+    ///
+    /// # Panics
+    ///
+    /// If vertex `v` is outside of the capacity of the graph, it will panic.
+    pub(crate) fn contains(&self, v: usize) -> bool {
+        self.entry(v).is_some()
+    }
+
+    /// Iterate over IDs of all alive vertices, in ascending order.
+    pub(crate) fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.entries).map(|i| self.u64_at(HEADER + i * ENTRY))
+    }
+
+    /// Find the position of the entry of the vertex in the mapping,
+    /// by binary search, if the vertex is alive.
+    fn entry(&self, v: usize) -> Option<usize> {
+        assert!(
+            v < self.capacity,
+            "Can't find ν{v} in the view of {} vertices",
+            self.capacity
+        );
+        let (mut low, mut high) = (0, self.entries);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let entry = HEADER + mid * ENTRY;
+            match self.u64_at(entry).cmp(&v) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Some(entry),
+            }
+        }
+        None
+    }
+
+    /// Read a `u32` number at the position in the mapping.
+    fn u32_at(&self, pos: usize) -> u32 {
+        u32::from_le_bytes(self.map[pos..pos + 4].try_into().unwrap())
+    }
+
+    /// Read a `u64` number at the position in the mapping, as `usize`.
+    fn u64_at(&self, pos: usize) -> usize {
+        usize::try_from(u64::from_le_bytes(
+            self.map[pos..pos + 8].try_into().unwrap(),
+        ))
+        .unwrap()
+    }
+}
+
+#[cfg(test)]
+use tempfile::TempDir;
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn reads_through_view() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.add(2);
+    g.bind(0, 1, Label::from_str("foo").unwrap());
+    g.bind(0, 2, Label::Alpha(0));
+    g.put(1, &Hex::from_str_bytes("hello"));
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodv");
+    g.save_view(file.as_path()).unwrap();
+    let view: SodgView<16> = SodgView::open(file.as_path()).unwrap();
+    assert_eq!(256, view.capacity());
+    assert_eq!(Some(2), view.kid(0, Label::Alpha(0)));
+    assert_eq!(None, view.kid(0, Label::Alpha(1)));
+    assert_eq!(2, view.kids(0).count());
+    assert_eq!(0, view.kids(5).count());
+    assert_eq!(Hex::from_str_bytes("hello"), view.data(1).unwrap());
+    assert!(view.data(2).is_none());
+}
+
+#[test]
+fn promotes_view() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("foo").unwrap());
+    g.put(1, &Hex::from(42));
+    g.add(7);
+    g.remove(7);
+    let next = g.next_id();
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodv");
+    g.save_view(file.as_path()).unwrap();
+    let view: SodgView<16> = SodgView::open(file.as_path()).unwrap();
+    let mut after = view.promote().unwrap();
+    assert_eq!(g, after);
+    assert_eq!(next + 1, after.next_id());
+    assert_eq!(Hex::from(42), after.data(1).unwrap());
+    assert!(view.data(1).is_some());
+}
+
+#[test]
+fn refuses_wrong_view() {
+    let mut g: Sodg<16> = Sodg::empty(100);
+    g.add(0);
+    let tmp = TempDir::new().unwrap();
+    let view = tmp.path().join("foo.sodv");
+    g.save_view(view.as_path()).unwrap();
+    let msg = format!("{:#}", SodgView::<1>::open(view.as_path()).err().unwrap());
+    assert!(msg.contains("Sodg<16>, while Sodg<1> is expected"), "{msg}");
+    let file = tmp.path().join("foo.sodg");
+    g.save(file.as_path()).unwrap();
+    let msg = format!("{:#}", SodgView::<16>::open(file.as_path()).err().unwrap());
+    assert!(msg.contains("it is not a view"), "{msg}");
+}
+
+#[test]
+fn saves_only_alive_vertices() {
+    let mut g: Sodg<16> = Sodg::empty(1_000_000);
+    g.add(0);
+    g.add(999_999);
+    g.bind(0, 999_999, Label::from_str("far").unwrap());
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodv");
+    let size = g.save_view(file.as_path()).unwrap();
+    assert!(size < 1000, "{size}");
+    let view: SodgView<16> = SodgView::open(file.as_path()).unwrap();
+    assert_eq!(Some(999_999), view.kid(0, Label::from_str("far").unwrap()));
+    assert_eq!(0, view.kids(42).count());
+    assert!(view.data(42).is_none());
+}
+
+#[test]
+fn promotes_lazily() {
+    let mut g: Sodg<16> = Sodg::empty(256);
+    g.add(0);
+    g.add(1);
+    g.bind(0, 1, Label::from_str("foo").unwrap());
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodv");
+    g.save_view(file.as_path()).unwrap();
+    let view: SodgView<16> = SodgView::open(file.as_path()).unwrap();
+    let mut after = view.promote().unwrap();
+    let kept = |g: &Sodg<16>| {
+        let f = g.vertices.fallback.as_ref().unwrap();
+        (
+            f.read.iter().filter(|c| c.get().is_some()).count(),
+            f.copied.iter().map(|b| b.count_ones()).sum::<u32>(),
+        )
+    };
+    assert_eq!(2, after.len());
+    assert_eq!(vec![0, 1], after.keys());
+    assert_eq!(2, after.next_id());
+    after.save(tmp.path().join("foo.sodg").as_path()).unwrap();
+    assert_eq!((0, 0), kept(&after));
+    assert_eq!(Some(1), after.kid(0, Label::from_str("foo").unwrap()));
+    assert_eq!((1, 0), kept(&after));
+    after.unbind(0, Label::from_str("foo").unwrap());
+    after.add(3);
+    assert_eq!((0, 2), kept(&after));
+    assert_eq!(None, after.kid(0, Label::from_str("foo").unwrap()));
+    assert_eq!(Some(1), view.kid(0, Label::from_str("foo").unwrap()));
+    assert_eq!(3, after.len());
+}
+
+#[test]
+fn refuses_broken_layout() {
+    let mut g: Sodg<16> = Sodg::empty(100);
+    g.add(0);
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("foo.sodv");
+    g.save_view(file.as_path()).unwrap();
+    let mut bytes = std::fs::read(file.as_path()).unwrap();
+    bytes[32..40].copy_from_slice(&u64::MAX.to_le_bytes());
+    std::fs::write(file.as_path(), bytes).unwrap();
+    let msg = format!("{:#}", SodgView::<16>::open(file.as_path()).err().unwrap());
+    assert!(msg.contains("the file is corrupted"), "{msg}");
+}